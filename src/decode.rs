@@ -0,0 +1,1060 @@
+// The pure part of the decoding: Base64 text -> bytes -> image segments.
+// Nothing here knows about egui, StreamDecoder in main.rs runs it on a worker thread.
+use base64::Engine as _;
+
+const BASE64_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/= ";
+const BASE64_URL_SAFE_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_= ";
+const HEX_ALPHABET: &str = "0123456789ABCDEFabcdef ";
+
+// Which flavour of Base64 the text is encoded with
+#[derive(Clone, Copy, PartialEq)]
+pub enum Base64Alphabet {
+    Standard, // + and /
+    UrlSafe,  // - and _
+    Hex,      // Not Base64 at all: a hex dump, two digits per byte
+}
+
+impl Base64Alphabet {
+    // Base64 data chars (A-Z, a-z, 0-9, +, /), everything else is ignored when decoding.
+    // ASCII only, is_alphanumeric() would let Cyrillic and full-width lookalikes through.
+    pub fn is_data_char(self, c: char) -> bool {
+        match self {
+            Base64Alphabet::Standard => c.is_ascii_alphanumeric() || c == '+' || c == '/',
+            Base64Alphabet::UrlSafe => c.is_ascii_alphanumeric() || c == '-' || c == '_',
+            Base64Alphabet::Hex => c.is_ascii_hexdigit(),
+        }
+    }
+
+    // Chars allowed on a line of the editor (data, padding and spaces)
+    pub fn valid_chars(self) -> &'static str {
+        match self {
+            Base64Alphabet::Standard => BASE64_ALPHABET,
+            Base64Alphabet::UrlSafe => BASE64_URL_SAFE_ALPHABET,
+            Base64Alphabet::Hex => HEX_ALPHABET,
+        }
+    }
+
+    // Hex never goes through the base64 engine, see decode_hex
+    pub fn engine_alphabet(self) -> &'static base64::alphabet::Alphabet {
+        match self {
+            Base64Alphabet::Standard => &base64::alphabet::STANDARD,
+            Base64Alphabet::UrlSafe => &base64::alphabet::URL_SAFE,
+            Base64Alphabet::Hex => unreachable!("hex is decoded without the base64 engine"),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Base64Alphabet::Standard => "standard (+/)",
+            Base64Alphabet::UrlSafe => "URL-safe (-_)",
+            Base64Alphabet::Hex => "hex",
+        }
+    }
+
+    // Chars that decode into a whole number of bytes: 4 chars -> 3 bytes, 2 hex digits -> 1 byte
+    pub fn group_chars(self) -> usize {
+        match self {
+            Base64Alphabet::Standard | Base64Alphabet::UrlSafe => 4,
+            Base64Alphabet::Hex => 2,
+        }
+    }
+
+    fn group_bytes(self) -> u64 {
+        match self {
+            Base64Alphabet::Standard | Base64Alphabet::UrlSafe => 3,
+            Base64Alphabet::Hex => 1,
+        }
+    }
+
+    fn bits_per_char(self) -> u64 {
+        match self {
+            Base64Alphabet::Standard | Base64Alphabet::UrlSafe => 6,
+            Base64Alphabet::Hex => 4,
+        }
+    }
+
+    // Binary offset of the byte the data char at `char_index` of the stream contributes to
+    pub fn binary_offset(self, char_index: u64) -> u64 {
+        let group = self.group_chars() as u64;
+        char_index / group * self.group_bytes() + (char_index % group) * self.bits_per_char() / 8
+    }
+
+    // Index of the first data char of the group the byte at `binary_offset` is decoded from
+    pub fn char_index(self, binary_offset: u64) -> u64 {
+        binary_offset / self.group_bytes() * self.group_chars() as u64
+    }
+}
+
+// Non-ASCII chars OCR emits instead of Base64 ones: Cyrillic/Greek letters, full-width digits...
+// They look right on screen, but the decode drops them and everything after is shifted.
+pub fn is_lookalike(c: char) -> bool {
+    !c.is_ascii() && (c.is_alphanumeric() || matches!(c, '＋' | '／' | '＝'))
+}
+
+// "U+0410 'А', U+FF11 '１'"
+pub fn describe_lookalikes(line: &str) -> Option<String> {
+    let found: Vec<String> = line
+        .chars()
+        .filter(|&c| is_lookalike(c))
+        .map(|c| format!("U+{:04X} '{}'", c as u32, c))
+        .collect();
+    (!found.is_empty()).then(|| found.join(", "))
+}
+
+// What trim_start / trim_end of a CleaningFilter count
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum TrimUnit {
+    #[default]
+    Chars,
+    Lines,
+}
+
+// Extra chars to strip while cleaning, on top of everything that isn't Base64 data.
+// The default strips nothing extra.
+#[derive(Clone, Default, PartialEq)]
+pub struct CleaningFilter {
+    pub strip_chars: String, // Specific chars, e.g. OCR noise that happens to be valid Base64
+    pub trim_start: usize,   // Skipped at the start of every page file, e.g. a header the OCR tool added
+    pub trim_end: usize,     // Skipped at the end of every page file, e.g. a page number
+    pub trim_unit: TrimUnit,
+}
+
+impl CleaningFilter {
+    fn is_active(&self) -> bool {
+        !self.strip_chars.is_empty()
+    }
+
+    fn strips(&self, c: char) -> bool {
+        self.strip_chars.contains(c)
+    }
+
+    fn trims(&self) -> bool {
+        self.trim_start > 0 || self.trim_end > 0
+    }
+
+    // The same filter for text that isn't a whole page file (scratch buffer, selection)
+    pub fn without_trim(&self) -> Self {
+        Self {
+            trim_start: 0,
+            trim_end: 0,
+            ..self.clone()
+        }
+    }
+
+    // A page file trimmed ahead of the decode, for a decode run with without_trim().
+    // The cut start is blanked out rather than removed, so char and line positions in the logs
+    // still match the file. `cut_short` leaves out trim_end, the text stops before the page does.
+    pub fn blank_trimmed(&self, text: &str, cut_short: bool) -> String {
+        let filter = if cut_short { Self { trim_end: 0, ..self.clone() } } else { self.clone() };
+        let (skipped, rest) = filter.trim(text);
+        let mut blanked: String = text
+            .chars()
+            .take(skipped)
+            .map(|c| if c == '\n' { '\n' } else { ' ' })
+            .collect();
+        blanked.push_str(rest);
+        blanked
+    }
+
+    // What is left of a page file after trim_start / trim_end,
+    // with the number of chars cut from its start (to map positions back to the file)
+    pub fn trim<'a>(&self, text: &'a str) -> (usize, &'a str) {
+        if !self.trims() {
+            return (0, text);
+        }
+
+        // Byte offset where every char or line starts
+        let starts: Vec<usize> = match self.trim_unit {
+            TrimUnit::Chars => text.char_indices().map(|(i, _)| i).collect(),
+            TrimUnit::Lines => text
+                .split_inclusive('\n')
+                .scan(0, |offset, line| {
+                    let start = *offset;
+                    *offset += line.len();
+                    Some(start)
+                })
+                .collect(),
+        };
+        if self.trim_start + self.trim_end >= starts.len() {
+            return (text.chars().count(), "");
+        }
+
+        let start = starts[self.trim_start];
+        let end = if self.trim_end == 0 {
+            text.len()
+        } else {
+            starts[starts.len() - self.trim_end]
+        };
+        (text[..start].chars().count(), &text[start..end])
+    }
+}
+
+// Base64 is decoded in chunks of this many chars (must be a multiple of 4)
+const DECODE_CHUNK_CHARS: usize = 4096;
+
+// (format, start, end) of an image in the decoded stream, end is None when truncated
+type ImageSegment = (image::ImageFormat, usize, Option<usize>);
+
+// An image (or what is left of it) found in the decoded stream
+pub struct ImageCandidate<'a> {
+    pub bytes: &'a [u8],
+    pub format: Option<image::ImageFormat>, // None when no magic was found and the format has to be guessed
+    pub offset: usize,                      // Where the segment starts in the decoded stream
+    pub truncated: bool,                    // No end marker, runs to the end of the stream
+}
+
+// We configure a custom engine to be tolerant of corruption (missing padding, trailing bits).
+fn permissive_engine(alphabet: Base64Alphabet) -> base64::engine::GeneralPurpose {
+    let config = base64::engine::GeneralPurposeConfig::new()
+        .with_decode_allow_trailing_bits(true)
+        .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent);
+
+    base64::engine::GeneralPurpose::new(alphabet.engine_alphabet(), config)
+}
+
+// The chars of `text` the decode keeps, same rules as decode_base64_stream()
+pub fn clean_base64(text: &str, alphabet: Base64Alphabet, filter: &CleaningFilter) -> String {
+    text.chars()
+        .filter(|&c| alphabet.is_data_char(c) && !filter.strips(c))
+        .collect()
+}
+
+// A whole page file as the stream decode sees it, trimmed and cleaned
+pub fn clean_page(text: &str, alphabet: Base64Alphabet, filter: &CleaningFilter) -> String {
+    clean_base64(filter.trim(text).1, alphabet, filter)
+}
+
+// Char index in `text` of every char clean_page() keeps, to map stream positions back to the page
+pub fn clean_page_positions(text: &str, alphabet: Base64Alphabet, filter: &CleaningFilter) -> Vec<usize> {
+    let (_, sources, _) = clean_with_sources(&[text.to_string()], alphabet, filter);
+    sources.into_iter().map(|(_, char_idx)| char_idx).collect()
+}
+
+// How often every data char of the alphabet occurs in cleaned Base64, in alphabet order.
+// Random bytes give every char about 1/64 of the total, an OCR substitution shows up
+// as one char too frequent and its lookalike too rare.
+pub fn char_histogram(clean: &str, alphabet: Base64Alphabet) -> Vec<(char, usize)> {
+    let mut counts: Vec<(char, usize)> = alphabet
+        .valid_chars()
+        .chars()
+        .filter(|&c| alphabet.is_data_char(c))
+        .map(|c| (c, 0))
+        .collect();
+    // Data chars are ASCII, so a table beats searching the 64 entries for every char
+    let mut slot = [None; 128];
+    for (i, &(c, _)) in counts.iter().enumerate() {
+        slot[c as usize] = Some(i);
+    }
+    for c in clean.chars() {
+        if let Some(&Some(i)) = slot.get(c as usize) {
+            counts[i].1 += 1;
+        }
+    }
+    counts
+}
+
+// Decodes already cleaned Base64 with the permissive engine (hex can't fail once cleaned)
+pub fn decode_clean_base64(clean: &str, alphabet: Base64Alphabet) -> Result<Vec<u8>, base64::DecodeError> {
+    if alphabet == Base64Alphabet::Hex {
+        return Ok(decode_hex(&clean.chars().collect::<Vec<_>>()));
+    }
+    permissive_engine(alphabet).decode(clean)
+}
+
+// Pairs of hex digits into bytes, a digit left over at the end is dropped
+fn decode_hex(clean: &[char]) -> Vec<u8> {
+    clean
+        .chunks_exact(2)
+        .filter_map(|pair| Some((pair[0].to_digit(16)? * 16 + pair[1].to_digit(16)?) as u8))
+        .collect()
+}
+
+// Cleans the concatenated files and decodes them as one Base64 stream.
+// On a failure the bytes decoded so far are returned and the logs point at the offending char.
+pub fn decode_base64_stream(
+    file_names: &[String],
+    file_contents: &[String],
+    alphabet: Base64Alphabet,
+    filter: &CleaningFilter,
+    logs: &mut Vec<String>,
+) -> Vec<u8> {
+    let raw_length: usize = file_contents.iter().map(|c| c.len()).sum();
+    logs.push(format!("Total raw length: {} characters", raw_length));
+
+    for (file_idx, content) in file_contents.iter().enumerate() {
+        for (line_idx, line) in content.lines().enumerate() {
+            if let Some(lookalikes) = describe_lookalikes(line) {
+                logs.push(format!(
+                    "WARNING: {} line {} has lookalike chars: {}",
+                    file_names[file_idx],
+                    line_idx + 1,
+                    lookalikes
+                ));
+            }
+        }
+    }
+
+    // 2. Clean Base64 Stream
+    // We strip everything that isn't a Base64 data char (A-Z, a-z, 0-9, +, /).
+    // We explicitly REMOVE existing '=' padding. The permissive decoder will 
+    // handle the necessary padding logic internally.
+    // For every kept char we remember (file index, char index in file) to report errors.
+    let (clean_chars, sources, filtered) = clean_with_sources(file_contents, alphabet, filter);
+
+    if filter.is_active() {
+        logs.push(format!("Cleaning filter stripped {} extra characters", filtered));
+    }
+    if filter.trims() {
+        logs.push(format!(
+            "Trimmed {} {} from the start and {} from the end of every file",
+            filter.trim_start,
+            if filter.trim_unit == TrimUnit::Lines { "lines" } else { "chars" },
+            filter.trim_end
+        ));
+        let mut per_file = vec![0; file_contents.len()];
+        for &(file_idx, _) in &sources {
+            per_file[file_idx] += 1;
+        }
+        for (name, length) in file_names.iter().zip(per_file) {
+            logs.push(format!("-> {}: {} Base64 characters left", name, length));
+        }
+    }
+
+    logs.push(format!("Cleaned Base64 length: {} characters", clean_chars.len()));
+
+    if alphabet == Base64Alphabet::Hex {
+        if clean_chars.len() % 2 != 0 {
+            logs.push("WARNING: Odd number of hex digits, one was lost or added somewhere (the last one is dropped)".into());
+        }
+        let bytes = decode_hex(&clean_chars);
+        logs.push(format!("Decoded {} hex digits into {} bytes of binary data", clean_chars.len(), bytes.len()));
+        return bytes;
+    }
+
+    // The permissive engine doesn't care, but a length that isn't a multiple of 4
+    // almost always means OCR lost or added characters somewhere
+    // (2 or 3 extra chars are also what a stream ending in '=' padding leaves behind)
+    let remainder = clean_chars.len() % 4;
+    if remainder == 1 {
+        logs.push("WARNING: Cleaned Base64 length is not a multiple of 4 (1 extra char), characters were lost or added".into());
+    } else if remainder != 0 {
+        logs.push(format!(
+            "WARNING: Cleaned Base64 length is not a multiple of 4 ({} extra chars), characters were probably lost or added unless the stream ends with {} '=' padding",
+            remainder,
+            4 - remainder
+        ));
+    }
+
+    // 3. Robust Decode
+    let engine = permissive_engine(alphabet);
+
+    // Decode in chunks (multiple of 4 chars, so the output is identical to a single decode)
+    // to be able to tell where the first failure happened.
+    let mut bytes = Vec::new();
+    for (chunk_idx, chunk) in clean_chars.chunks(DECODE_CHUNK_CHARS).enumerate() {
+        let chunk_string: String = chunk.iter().collect();
+        match engine.decode(&chunk_string) {
+            Ok(decoded) => bytes.extend_from_slice(&decoded),
+            Err(e) => {
+                // Error offsets are in bytes of the chunk, convert back to chars
+                let byte_offset = match e {
+                    base64::DecodeError::InvalidByte(offset, _)
+                    | base64::DecodeError::InvalidLastSymbol(offset, _) => offset,
+                    _ => chunk_string.len().saturating_sub(1),
+                };
+                let char_in_chunk = chunk_string
+                    .char_indices()
+                    .take_while(|(i, _)| *i < byte_offset)
+                    .count()
+                    .min(chunk.len() - 1);
+                let clean_idx = chunk_idx * DECODE_CHUNK_CHARS + char_in_chunk;
+                let (file_idx, char_idx) = sources[clean_idx];
+                let line = line_of(&file_contents[file_idx], char_idx);
+
+                logs.push(format!("CRITICAL: Base64 decoding failed even with permissive mode: {}", e));
+                logs.push(format!(
+                    "-> First failure near {} char {} (line {}), Base64 index {}, binary offset 0x{:X}",
+                    file_names[file_idx],
+                    char_idx,
+                    line,
+                    clean_idx,
+                    bytes.len()
+                ));
+                break;
+            }
+        }
+    }
+
+    logs.push(format!("Decoded into {} bytes of binary data", bytes.len()));
+    bytes
+}
+
+// The stream decoded with the first `skip` data chars dropped, for trying the other alignments
+// of the 4-char groups. No logs, decode_base64_stream explains the regular alignment.
+pub fn decode_stream_skipping(
+    file_contents: &[String],
+    alphabet: Base64Alphabet,
+    filter: &CleaningFilter,
+    skip: usize,
+) -> Vec<u8> {
+    let clean: String = file_contents.iter().map(|content| clean_page(content, alphabet, filter)).collect();
+    // Data chars are ASCII, so chars and bytes are the same here
+    let mut shifted = clean.get(skip..).unwrap_or_default().to_string();
+    let group = alphabet.group_chars();
+    shifted.truncate(shifted.len() / group * group);
+    decode_clean_base64(&shifted, alphabet).unwrap_or_default()
+}
+
+// Images of known format in the stream that decode completely, to compare alignments
+pub fn count_decodable_images(bytes: &[u8]) -> usize {
+    find_image_candidates(bytes, &mut Vec::new())
+        .iter()
+        .filter(|candidate| {
+            candidate
+                .format
+                .is_some_and(|format| image::load_from_memory_with_format(candidate.bytes, format).is_ok())
+        })
+        .count()
+}
+
+// Every value a single data char can take: the 64 Base64 chars, or the 16 hex digits
+// (one case is enough, both decode the same)
+pub fn possible_chars(alphabet: Base64Alphabet) -> Vec<char> {
+    alphabet
+        .valid_chars()
+        .chars()
+        .filter(|&c| alphabet.is_data_char(c) && !(alphabet == Base64Alphabet::Hex && c.is_ascii_lowercase()))
+        .collect()
+}
+
+// How far a stream decodes, to rank the values tried for an unknown char:
+// (images that decode completely, JPEG rows a strict decode gets through before the corruption)
+pub fn decode_score(bytes: &[u8]) -> (usize, usize) {
+    let jpeg_rows = find_image_candidates(bytes, &mut Vec::new())
+        .iter()
+        .filter(|candidate| candidate.format == Some(image::ImageFormat::Jpeg))
+        .filter_map(|candidate| decode_partial_jpeg(candidate.bytes, true))
+        .map(|partial| partial.decoded_rows)
+        .sum();
+    (count_decodable_images(bytes), jpeg_rows)
+}
+
+// Some streams are the Base64 of gzip or zlib data, the image only shows up once inflated
+#[derive(Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zlib,
+}
+
+impl Compression {
+    // Gzip starts with 1F 8B. Zlib with 78 (deflate, 32K window), and the two header bytes
+    // read as a big endian number are a multiple of 31.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0x1F, 0x8B, ..] => Some(Self::Gzip),
+            [0x78, flags, ..] if (0x7800 + *flags as u16) % 31 == 0 => Some(Self::Zlib),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zlib => "zlib",
+        }
+    }
+}
+
+// The inflated stream when it has a gzip or zlib header and inflates cleanly.
+// None otherwise, the caller goes on with the raw bytes.
+pub fn decompress(bytes: &[u8], logs: &mut Vec<String>) -> Option<Vec<u8>> {
+    use std::io::Read as _;
+
+    let Some(compression) = Compression::detect(bytes) else {
+        logs.push("No gzip (1F 8B) or zlib (78 ..) header, decompression not attempted".into());
+        return None;
+    };
+    logs.push(format!("Found a {} header, decompressing...", compression.name()));
+
+    let mut inflated = Vec::new();
+    let result = match compression {
+        Compression::Gzip => flate2::read::GzDecoder::new(bytes).read_to_end(&mut inflated),
+        Compression::Zlib => flate2::read::ZlibDecoder::new(bytes).read_to_end(&mut inflated),
+    };
+    match result {
+        Ok(_) => {
+            logs.push(format!(
+                "Decompressed {} bytes into {} bytes",
+                bytes.len(),
+                inflated.len()
+            ));
+            Some(inflated)
+        }
+        Err(e) => {
+            logs.push(format!(
+                "WARNING: Decompression failed after {} bytes ({}), scanning the raw bytes instead",
+                inflated.len(),
+                e
+            ));
+            None
+        }
+    }
+}
+
+// The data chars of all (trimmed) files, every one with the (file index, char index in file)
+// it came from, and how many chars the cleaning filter stripped
+fn clean_with_sources(
+    file_contents: &[String],
+    alphabet: Base64Alphabet,
+    filter: &CleaningFilter,
+) -> (Vec<char>, Vec<(usize, usize)>, usize) {
+    let mut clean_chars = Vec::new();
+    let mut sources = Vec::new();
+    let mut filtered = 0;
+    for (file_idx, content) in file_contents.iter().enumerate() {
+        let (skipped, content) = filter.trim(content);
+        for (char_idx, c) in content.chars().enumerate() {
+            let char_idx = skipped + char_idx; // In the whole file
+            if !alphabet.is_data_char(c) {
+                continue;
+            }
+            if filter.strips(c) {
+                filtered += 1;
+                continue;
+            }
+            clean_chars.push(c);
+            sources.push((file_idx, char_idx));
+        }
+    }
+    (clean_chars, sources, filtered)
+}
+
+// 1-based line of the char at `char_idx`
+fn line_of(content: &str, char_idx: usize) -> usize {
+    content.chars().take(char_idx).filter(|&c| c == '\n').count() + 1
+}
+
+// Decodes the stream, encodes the bytes again and compares the result with the cleaned input.
+// What the permissive decode quietly accepts (stray bits in the last char of a group,
+// a stream cut short by a failure) shows up as the first Base64 index where the two differ.
+// Padding is ignored on both sides. Returns true when they match.
+pub fn verify_round_trip(
+    file_names: &[String],
+    file_contents: &[String],
+    alphabet: Base64Alphabet,
+    filter: &CleaningFilter,
+    logs: &mut Vec<String>,
+) -> bool {
+    let bytes = decode_base64_stream(file_names, file_contents, alphabet, filter, logs);
+    let (clean_chars, sources, _) = clean_with_sources(file_contents, alphabet, filter);
+
+    let encoded = if alphabet == Base64Alphabet::Hex {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    } else {
+        let config = base64::engine::GeneralPurposeConfig::new().with_encode_padding(false);
+        base64::engine::GeneralPurpose::new(alphabet.engine_alphabet(), config).encode(&bytes)
+    };
+    let encoded: Vec<char> = encoded.chars().collect();
+
+    // Hex digits are the same byte in either case
+    let mismatch = clean_chars
+        .iter()
+        .zip(&encoded)
+        .position(|(a, b)| if alphabet == Base64Alphabet::Hex { !a.eq_ignore_ascii_case(b) } else { a != b })
+        .or_else(|| (clean_chars.len() != encoded.len()).then_some(clean_chars.len().min(encoded.len())));
+
+    let Some(idx) = mismatch else {
+        logs.push(format!(
+            "Round trip OK: {} bytes re-encode to the same {} Base64 chars",
+            bytes.len(),
+            clean_chars.len()
+        ));
+        return true;
+    };
+
+    // Same as the hex jump, the byte the differing char contributes to
+    let binary_offset = alphabet.binary_offset(idx as u64);
+    let location = match sources.get(idx) {
+        Some(&(file_idx, char_idx)) => format!(
+            "{} char {} (line {})",
+            file_names[file_idx],
+            char_idx,
+            line_of(&file_contents[file_idx], char_idx)
+        ),
+        None => "the end of the input".to_string(),
+    };
+    let describe = |c: Option<&char>| c.map_or("nothing".to_string(), |c| format!("'{}'", c));
+    logs.push(format!(
+        "WARNING: Round trip differs at Base64 index {}, binary offset 0x{:X}, near {}: input has {}, re-encoded has {}",
+        idx,
+        binary_offset,
+        location,
+        describe(clean_chars.get(idx)),
+        describe(encoded.get(idx))
+    ));
+    logs.push(format!(
+        "-> Cleaned input is {} chars, re-encoded {} chars",
+        clean_chars.len(),
+        encoded.len()
+    ));
+    false
+}
+
+
+// Undecoded rows of a partial JPEG keep this value, a neutral gray
+const PARTIAL_JPEG_FILL: u8 = 0x80;
+
+// A JPEG the strict decode gave up on, with the rows that came before the corruption
+pub struct PartialJpeg {
+    pub width: usize,
+    pub height: usize,
+    pub rgb: Vec<u8>,          // width * height * 3, rows past the corruption are gray
+    pub decoded_rows: usize,   // From the top
+    pub error: Option<String>, // What stopped the decoder, None if it got through
+}
+
+// Decode with zune-jpeg: it writes the pixels into our buffer row by row, so when it fails
+// the rows before the corruption are still there. Lenient mode skips what it can and fills
+// a missing tail, strict mode stops at the first problem. Progressive JPEGs are only
+// written out at the end and come back empty. None when even the headers are unreadable.
+pub fn decode_partial_jpeg(bytes: &[u8], strict: bool) -> Option<PartialJpeg> {
+    use zune_jpeg::zune_core::colorspace::ColorSpace;
+    use zune_jpeg::zune_core::options::DecoderOptions;
+
+    let options = DecoderOptions::default()
+        .set_strict_mode(strict)
+        .jpeg_set_out_colorspace(ColorSpace::RGB);
+    let mut decoder = zune_jpeg::JpegDecoder::new_with_options(std::io::Cursor::new(bytes), options);
+    decoder.decode_headers().ok()?;
+    let (width, height) = decoder.dimensions()?;
+    let mut rgb = vec![PARTIAL_JPEG_FILL; decoder.output_buffer_size()?];
+
+    let error = decoder.decode_into(&mut rgb).err().map(|e| format!("{:?}", e));
+
+    // Rows nothing was written to still hold the fill value
+    let untouched_rows = rgb
+        .chunks(width * 3)
+        .rev()
+        .take_while(|row| row.iter().all(|&b| b == PARTIAL_JPEG_FILL))
+        .count();
+    Some(PartialJpeg {
+        width,
+        height,
+        rgb,
+        decoded_rows: height.saturating_sub(untouched_rows),
+        error,
+    })
+}
+
+// Splits the decoded stream into the images it contains.
+// Without any known magic the whole stream is returned as a single candidate.
+pub fn find_image_candidates<'a>(bytes: &'a [u8], logs: &mut Vec<String>) -> Vec<ImageCandidate<'a>> {
+    // ROBUST SCANNER: Looks for the magic bytes of every known format
+    // (SOI FF D8 for JPEG, 89 50 4E 47 for PNG, 47 49 46 for GIF, RIFF....WEBP for WebP,
+    // 42 4D for BMP) and where each image ends
+    let segments = find_image_segments(bytes);
+    if segments.is_empty() {
+        logs.push("-> No known image magic found, guessing the format of the whole stream".into());
+        return vec![ImageCandidate {
+            bytes,
+            format: None,
+            offset: 0,
+            truncated: false,
+        }];
+    }
+
+    logs.push(format!("Found {} image segment(s)", segments.len()));
+    segments
+        .into_iter()
+        .map(|(format, start, end)| ImageCandidate {
+            bytes: &bytes[start..end.unwrap_or(bytes.len())],
+            format: Some(format),
+            offset: start,
+            truncated: end.is_none(),
+        })
+        .collect()
+}
+
+// IEND chunk type followed by its fixed CRC, the end of every PNG
+const PNG_IEND: [u8; 8] = [0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82];
+
+// Image signatures and end markers in the stream as (offset, length), for the hex dump
+pub fn find_magic_bytes(bytes: &[u8]) -> Vec<(usize, usize)> {
+    let mut marks = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let length = match sniff_image_format(rest) {
+            Some(image::ImageFormat::Png) => 8,
+            Some(image::ImageFormat::Gif) => 6,
+            Some(image::ImageFormat::WebP) => 12, // RIFF, size, WEBP
+            Some(_) => 2,                         // JPEG SOI and BMP "BM"
+            None if rest.starts_with(&[0xFF, 0xD9]) => 2, // JPEG EOI
+            None if rest.starts_with(&PNG_IEND) => PNG_IEND.len(),
+            None => 0,
+        };
+        if length > 0 {
+            marks.push((i, length));
+            i += length;
+        } else {
+            i += 1;
+        }
+    }
+    marks
+}
+
+fn sniff_image_format(bytes: &[u8]) -> Option<image::ImageFormat> {
+    // JPEG SOI is always followed by another marker, requiring it avoids most false positives
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(image::ImageFormat::Jpeg)
+    } else if bytes.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some(image::ImageFormat::Png)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(image::ImageFormat::Gif)
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some(image::ImageFormat::WebP)
+    } else if is_bmp_header(bytes) {
+        Some(image::ImageFormat::Bmp)
+    } else {
+        None
+    }
+}
+
+// "BM" alone turns up in random data all the time, so the rest of the header has to make sense too:
+// reserved fields are zero and the DIB header has one of the known sizes
+fn is_bmp_header(bytes: &[u8]) -> bool {
+    const DIB_HEADER_SIZES: [u32; 6] = [12, 40, 52, 56, 108, 124];
+    if !bytes.starts_with(b"BM") || bytes.len() < 18 {
+        return false;
+    }
+    let dib_size = u32::from_le_bytes([bytes[14], bytes[15], bytes[16], bytes[17]]);
+    bytes[6..10] == [0, 0, 0, 0] && DIB_HEADER_SIZES.contains(&dib_size)
+}
+
+// Returns (format, start, end) of every image in the stream, end is None for a truncated one.
+fn find_image_segments(bytes: &[u8]) -> Vec<ImageSegment> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let Some(format) = sniff_image_format(&bytes[i..]) else {
+            i += 1;
+            continue;
+        };
+
+        let end = match format {
+            image::ImageFormat::Jpeg => find_jpeg_end(bytes, i),
+            image::ImageFormat::Png => bytes[i..]
+                .windows(PNG_IEND.len())
+                .position(|w| w == PNG_IEND)
+                .map(|pos| i + pos + PNG_IEND.len()),
+            // RIFF chunk size (little endian) counts everything after the 8 byte chunk header
+            image::ImageFormat::WebP => {
+                let size = u32::from_le_bytes([bytes[i + 4], bytes[i + 5], bytes[i + 6], bytes[i + 7]]);
+                Some(i + 8 + size as usize).filter(|&end| end <= bytes.len())
+            }
+            // The header stores the size of the whole file
+            image::ImageFormat::Bmp => {
+                let size = u32::from_le_bytes([bytes[i + 2], bytes[i + 3], bytes[i + 4], bytes[i + 5]]);
+                Some(i + size as usize).filter(|&end| end <= bytes.len())
+            }
+            // GIF has no reliable end marker, it runs until the next image (or the end)
+            _ => Some(
+                (i + 6..bytes.len())
+                    .find(|&j| sniff_image_format(&bytes[j..]).is_some())
+                    .unwrap_or(bytes.len()),
+            ),
+        };
+
+        segments.push((format, i, end));
+        match end {
+            Some(end) => i = end,
+            None => break, // Truncated image swallows the rest of the stream
+        }
+    }
+
+    segments
+}
+
+// Finds the EOI matching the SOI at `start`. SOI/EOI are counted by depth,
+// so an embedded EXIF thumbnail does not end the outer image.
+fn find_jpeg_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i + 1 < bytes.len() {
+        let is_soi = bytes[i] == 0xFF && bytes[i + 1] == 0xD8 && bytes.get(i + 2) == Some(&0xFF);
+        let is_eoi = bytes[i] == 0xFF && bytes[i + 1] == 0xD9;
+
+        if is_soi {
+            depth += 1;
+            i += 2;
+        } else if is_eoi {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+// EXIF fields worth showing to check a recovered photo is the right one
+const EXIF_FIELDS: [(u16, &str); 8] = [
+    (0x010F, "Camera make"),
+    (0x0110, "Camera model"),
+    (0x9003, "Taken"),
+    (0x0132, "Modified"),
+    (0xA002, "Original width"),
+    (0xA003, "Original height"),
+    (0x0112, "Orientation"),
+    (0x0131, "Software"),
+];
+// IFD0 entry pointing to the Exif sub-IFD, where the capture details live
+const EXIF_IFD_POINTER: u16 = 0x8769;
+
+// Reads the EXIF fields of a JPEG as (label, value), empty when it has none (or it got cut off)
+pub fn read_exif(bytes: &[u8]) -> Vec<(&'static str, String)> {
+    let Some(tiff) = find_exif_segment(bytes).and_then(TiffReader::new) else {
+        return Vec::new();
+    };
+
+    let mut fields = Vec::new();
+    let mut ifds = vec![tiff.u32(4)];
+    while let Some(Some(ifd)) = ifds.pop() {
+        let ifd = ifd as usize;
+        let Some(count) = tiff.u16(ifd) else {
+            continue;
+        };
+        for i in 0..count as usize {
+            let entry = ifd + 2 + i * 12;
+            let Some(tag) = tiff.u16(entry) else {
+                break;
+            };
+            // Only IFD0 points to the Exif IFD, so this can't loop
+            if tag == EXIF_IFD_POINTER {
+                ifds.push(tiff.u32(entry + 8));
+            }
+            if let Some(position) = EXIF_FIELDS.iter().position(|&(t, _)| t == tag) {
+                if let Some(value) = tiff.value(entry) {
+                    fields.push((position, value));
+                }
+            }
+        }
+    }
+
+    fields.sort_by_key(|&(position, _)| position);
+    fields
+        .into_iter()
+        .map(|(position, value)| (EXIF_FIELDS[position].1, value))
+        .collect()
+}
+
+// Payload of the APP1 "Exif" segment, it sits among the markers before the scan data
+fn find_exif_segment(bytes: &[u8]) -> Option<&[u8]> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut i = 2;
+    while bytes.get(i) == Some(&0xFF) {
+        let marker = *bytes.get(i + 1)?;
+        if marker == 0xDA || marker == 0xD9 {
+            return None; // SOS or EOI, no metadata comes after this
+        }
+        let length = u16::from_be_bytes([*bytes.get(i + 2)?, *bytes.get(i + 3)?]) as usize;
+        let payload = bytes.get(i + 4..i + 2 + length)?;
+        if marker == 0xE1 && payload.starts_with(b"Exif\0\0") {
+            return Some(&payload[6..]);
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+// EXIF is a small TIFF file, offsets are relative to its header
+struct TiffReader<'a> {
+    data: &'a [u8],
+    little_endian: bool, // "II" (Intel) or "MM" (Motorola)
+}
+
+impl<'a> TiffReader<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        Some(Self { data, little_endian })
+    }
+
+    fn u16(&self, pos: usize) -> Option<u16> {
+        let b: [u8; 2] = self.data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    }
+
+    fn u32(&self, pos: usize) -> Option<u32> {
+        let b: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    // Value of the 12-byte IFD entry at `entry`, for the types the fields above use
+    fn value(&self, entry: usize) -> Option<String> {
+        let kind = self.u16(entry + 2)?;
+        let count = self.u32(entry + 4)? as usize;
+        match kind {
+            // ASCII, stored in the entry itself when it fits in 4 bytes
+            2 => {
+                let start = if count <= 4 { entry + 8 } else { self.u32(entry + 8)? as usize };
+                let text = self.data.get(start..start + count)?;
+                let text = String::from_utf8_lossy(text);
+                let text = text.trim_end_matches('\0').trim();
+                (!text.is_empty()).then(|| text.to_string())
+            }
+            3 => self.u16(entry + 8).map(|v| v.to_string()), // SHORT
+            4 => self.u32(entry + 8).map(|v| v.to_string()), // LONG
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine as _;
+
+    // A small JPEG (SOI, APP0, some scan data, EOI) followed by a PNG that is only its
+    // signature and IEND, enough for the segment scanner. 228 bytes, no '=' padding.
+    const JPEG_LENGTH: usize = 6 + 5 + 199 + 2;
+
+    fn fixture_bytes() -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        bytes.extend_from_slice(b"JFIF\0");
+        bytes.extend(0..199u8); // No 0xFF, so no marker in the data
+        bytes.extend_from_slice(&[0xFF, 0xD9]);
+        bytes.extend_from_slice(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]);
+        bytes.extend_from_slice(&PNG_IEND);
+        bytes
+    }
+
+    // The fixture as the PDF has it: 76 chars per line, split over two page files
+    fn fixture_pages() -> (Vec<String>, Vec<String>) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(fixture_bytes());
+        let lines: Vec<&str> = encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|line| std::str::from_utf8(line).unwrap())
+            .collect();
+        let half = lines.len() / 2;
+        (
+            vec!["page001.txt".to_string(), "page002.txt".to_string()],
+            vec![lines[..half].join("\n") + "\n", lines[half..].join("\n") + "\n"],
+        )
+    }
+
+    #[test]
+    fn decodes_a_clean_stream_across_pages() {
+        let (names, contents) = fixture_pages();
+        let mut logs = Vec::new();
+        let bytes = decode_base64_stream(&names, &contents, Base64Alphabet::Standard, &CleaningFilter::default(), &mut logs);
+
+        assert_eq!(bytes, fixture_bytes());
+        assert!(!logs.iter().any(|msg| msg.starts_with("CRITICAL") || msg.starts_with("WARNING")), "{:?}", logs);
+    }
+
+    #[test]
+    fn decodes_a_truncated_stream_up_to_the_cut() {
+        let (names, mut contents) = fixture_pages();
+        // Only the first line of the second page is left, the cut is inside the JPEG
+        contents[1] = contents[1].lines().next().unwrap().to_string();
+        let mut logs = Vec::new();
+        let bytes = decode_base64_stream(&names, &contents, Base64Alphabet::Standard, &CleaningFilter::default(), &mut logs);
+
+        assert_eq!(bytes.len(), 3 * 76 / 4 * 3);
+        assert_eq!(bytes[..], fixture_bytes()[..bytes.len()]);
+        assert_eq!(find_image_segments(&bytes), vec![(image::ImageFormat::Jpeg, 0, None)]);
+    }
+
+    #[test]
+    fn reports_where_the_decode_failed() {
+        // 9 chars: the last one can't make a byte on its own
+        let names = vec!["page001.txt".to_string(), "page002.txt".to_string()];
+        let contents = vec!["QUJD\n".to_string(), "RUZH\nS".to_string()];
+        let mut logs = Vec::new();
+        decode_base64_stream(&names, &contents, Base64Alphabet::Standard, &CleaningFilter::default(), &mut logs);
+
+        assert!(logs.iter().any(|msg| msg.contains("not a multiple of 4 (1 extra char)")), "{:?}", logs);
+        assert!(logs.iter().any(|msg| msg.starts_with("CRITICAL")), "{:?}", logs);
+        assert!(
+            logs.iter().any(|msg| msg
+                == "-> First failure near page002.txt char 5 (line 2), Base64 index 8, binary offset 0x0"),
+            "{:?}",
+            logs
+        );
+    }
+
+    #[test]
+    fn finds_the_images_of_the_stream() {
+        let mut bytes = vec![0x00, 0x12, 0x34]; // Garbage before the first image
+        bytes.extend(fixture_bytes());
+
+        assert_eq!(
+            find_image_segments(&bytes),
+            vec![
+                (image::ImageFormat::Jpeg, 3, Some(3 + JPEG_LENGTH)),
+                (image::ImageFormat::Png, 3 + JPEG_LENGTH, Some(bytes.len())),
+            ]
+        );
+    }
+
+    #[test]
+    fn an_embedded_thumbnail_does_not_end_the_jpeg() {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x01, 0x02];
+        bytes.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xDB, 0x03, 0xFF, 0xD9]); // The EXIF thumbnail
+        bytes.extend_from_slice(&[0x04, 0x05, 0xFF, 0xD9]);
+
+        assert_eq!(find_image_segments(&bytes), vec![(image::ImageFormat::Jpeg, 0, Some(bytes.len()))]);
+    }
+
+    // OCR lookalikes are dropped like any other non-Base64 char, never taken for the Latin ones
+    #[test]
+    fn cyrillic_lookalikes_are_cleaned_out() {
+        let filter = CleaningFilter::default();
+        // Cyrillic А, В, С, Е, О among the Base64 of "ABCEFGIJK"
+        let ocr = "QUJD\u{0410}RUZH\u{0412}\u{0421}\nSUpL\u{0415}\u{041E}\n";
+        let latin = "QUJDRUZH\nSUpL\n";
+
+        assert_eq!(clean_page(ocr, Base64Alphabet::Standard, &filter), "QUJDRUZHSUpL");
+
+        let names = vec!["page001.txt".to_string()];
+        let mut logs = Vec::new();
+        let bytes = decode_base64_stream(&names, &[ocr.to_string()], Base64Alphabet::Standard, &filter, &mut logs);
+        assert_eq!(bytes, b"ABCEFGIJK");
+        assert_eq!(
+            bytes,
+            decode_base64_stream(&names, &[latin.to_string()], Base64Alphabet::Standard, &filter, &mut Vec::new())
+        );
+        assert!(logs.iter().any(|msg| msg.contains("lookalike chars")), "{:?}", logs);
+    }
+
+    // Decode to Cursor trims ahead of the decode, the positions in the logs must not move
+    #[test]
+    fn blank_trimmed_keeps_positions() {
+        let filter = CleaningFilter {
+            trim_start: 1,
+            trim_end: 1,
+            trim_unit: TrimUnit::Lines,
+            ..CleaningFilter::default()
+        };
+        let page = "HEAD\nQUJD\nRUZH\nFOOT\n";
+
+        assert_eq!(filter.blank_trimmed(page, false), "    \nQUJD\nRUZH\n");
+        // Stops at the cursor, the last lines are data, not the footer
+        assert_eq!(filter.blank_trimmed("HEAD\nQUJD\nRUZH\n", true), "    \nQUJD\nRUZH\n");
+        assert_eq!(filter.without_trim().blank_trimmed(page, false), page);
+    }
+}
@@ -0,0 +1,95 @@
+// Line diff of two texts, to compare a page with the text of its PDF or with another page.
+// A plain LCS table: pages are a few hundred lines at most, so O(n * m) is fine.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiffOp {
+    Same,
+    Removed, // Only in the old text
+    Added,   // Only in the new text
+}
+
+pub struct DiffLine<'a> {
+    pub op: DiffOp,
+    pub text: &'a str,
+    pub old_line: Option<usize>, // 0-based, None for added lines
+    pub new_line: Option<usize>, // 0-based, None for removed lines
+}
+
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let (n, m) = (old_lines.len(), new_lines.len());
+
+    // lcs[i][j] = length of the LCS of old_lines[i..] and new_lines[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old_lines[i] == new_lines[j] {
+            result.push(DiffLine {
+                op: DiffOp::Same,
+                text: old_lines[i],
+                old_line: Some(i),
+                new_line: Some(j),
+            });
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removed before added, like a unified diff
+            result.push(DiffLine {
+                op: DiffOp::Removed,
+                text: old_lines[i],
+                old_line: Some(i),
+                new_line: None,
+            });
+            i += 1;
+        } else {
+            result.push(DiffLine {
+                op: DiffOp::Added,
+                text: new_lines[j],
+                old_line: None,
+                new_line: Some(j),
+            });
+            j += 1;
+        }
+    }
+    result
+}
+
+// Moves char positions of `old` to where they are in `new`, for marks that have to follow
+// the edits. The edit is taken as the span between the common prefix and suffix: positions
+// before it stay, positions after it shift, positions inside it are dropped (overwritten).
+pub fn shift_positions(old: &str, new: &str, positions: &mut Vec<usize>) {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(old.len().min(new.len()) - prefix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+
+    positions.retain_mut(|position| {
+        if *position < prefix {
+            true
+        } else if *position >= old_end {
+            *position = *position + new_end - old_end;
+            true
+        } else {
+            false
+        }
+    });
+}
@@ -1,954 +1,4771 @@
 use eframe::egui;
 use eframe::emath;
 use pdfium_render::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod decode;
+mod diff;
+mod settings;
+
+use decode::{Base64Alphabet, CleaningFilter, TrimUnit};
+use settings::{HighlightStyle, LineEnding, Settings};
 
 fn main() -> Result<(), eframe::Error> {
+    let args = CliArgs::parse();
+    if args.headless {
+        std::process::exit(run_headless(&args));
+    }
+
     // 1. Setup PDFium
-    // Ensure the dynamic library (dll/dylib/so) is available at runtime
-    let pdfium = Pdfium::new(
-        Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
-            .or_else(|_| Pdfium::bind_to_system_library())
-            .expect(
-                "Could not load PDFium library. Please ensure the dynamic library is available.",
-            ),
-    );
-
-    // Sadly, this thing loads a C++ library and has to live forever
-    let pdfium_static: &'static Pdfium = Box::leak(Box::new(pdfium));
-
-    // 2. Load File from CLI
-    let args: Vec<String> = env::args().collect();
-    let file_path = if args.len() > 1 {
-        args[1].clone()
-    } else {
-        eprintln!("Usage: cargo run -- <path_to_pdf>");
-        "test.pdf".to_string()
-    };
+    // Ensure the dynamic library (dll/dylib/so) is available at runtime.
+    // Without it the window still opens, explaining where to get it (the Scratch decoder works anyway).
+    let pdfium = Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
+        .or_else(|_| Pdfium::bind_to_system_library())
+        // Sadly, this thing loads a C++ library and has to live forever
+        .map(|bindings| -> &'static Pdfium { Box::leak(Box::new(Pdfium::new(bindings))) })
+        .map_err(|e| pdfium_missing_message(&format!("{:?}", e)));
+    if let Err(msg) = &pdfium {
+        eprintln!("{}", msg);
+    }
 
-    // 3. Initialize App State
+    // 2. Initialize App State
+    // eframe restores the window size and position of the last session (persist_window),
+    // only the very first run, with nothing saved yet, starts maximized
+    let first_run = eframe::storage_dir(APP_NAME).is_none_or(|dir| !dir.join("app.ron").exists());
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_maximized(true),
+        viewport: egui::ViewportBuilder::default().with_maximized(first_run),
+        persist_window: true,
         ..Default::default()
     };
 
     eframe::run_native(
-        "PDF text to JPEG utility",
+        APP_NAME,
         options,
-        Box::new(|cc| Ok(Box::new(PdfApp::new(cc, pdfium_static, file_path)))),
+        Box::new(|cc| Ok(Box::new(PdfApp::new(cc, pdfium, args)))),
+    )
+}
+
+// Shown instead of the page text when the PDFium library could not be loaded
+fn pdfium_missing_message(error: &str) -> String {
+    let current_dir = env::current_dir().unwrap_or_default();
+    format!(
+        "Could not load the PDFium library ({}).\n\n\
+         PDF pages can't be shown without it. Download the build for your platform from\n\
+         https://github.com/bblanchon/pdfium-binaries/releases\n\
+         and place {} from its lib (or bin on Windows) folder in\n\
+         {}\n\
+         then start the utility again.\n\n\
+         Meanwhile Base64 pasted into the Scratch tab below can still be decoded.",
+        error,
+        Pdfium::pdfium_platform_library_name().to_string_lossy(),
+        current_dir.display()
+    )
+}
+
+// Decodes the page files in the workdir without opening a window.
+// Returns the process exit code: 0 if at least one image was recovered.
+fn run_headless(args: &CliArgs) -> i32 {
+    let workdir = match (&args.workdir, args.file_paths.first()) {
+        (Some(workdir), _) => workdir.clone(),
+        (None, Some(file_path)) => default_workdir(file_path),
+        (None, None) => PathBuf::from("."),
+    };
+
+    let settings = Settings::load();
+    let (tx, rx) = mpsc::channel();
+    let decoder = StreamDecoder {
+        tx,
+        ctx: None,
+        alphabet: Base64Alphabet::Standard,
+        filter: CleaningFilter::default(),
+        per_page: false,
+        recursive: args.recursive,
+        decompress: args.decompress,
+        try_alignments: false,
+        partial_jpeg: settings.partial_jpeg_recovery,
+        page_files: PageFilePattern::of(&settings),
+    };
+    let decoder_workdir = workdir.clone();
+    std::thread::spawn(move || decoder.run(&decoder_workdir));
+
+    let mut recovered = 0;
+    for msg in rx {
+        match msg {
+            DecodeMessage::Log(msg) => println!("{}", msg),
+            DecodeMessage::Image { bytes, format, .. } => {
+                recovered += 1;
+                let path = recovered_image_path(&workdir, recovered, format);
+                match fs::write(&path, &bytes) {
+                    Ok(_) => println!("Saved {}", path.display()),
+                    Err(e) => eprintln!("Error saving {}: {}", path.display(), e),
+                }
+                for (label, value) in decode::read_exif(&bytes) {
+                    println!("  {}: {}", label, value);
+                }
+            }
+            DecodeMessage::Progress(_)
+            | DecodeMessage::DecodedBytes(_)
+            | DecodeMessage::Truncated
+            | DecodeMessage::Source(_) => {}
+        }
+    }
+
+    if recovered > 0 { 0 } else { 1 }
+}
+
+// recovered_001.jpg, recovered_002.png...
+fn recovered_image_path(workdir: &Path, number: usize, format: Option<image::ImageFormat>) -> PathBuf {
+    let extension = format
+        .and_then(|f| f.extensions_str().first().copied())
+        .unwrap_or("bin");
+    workdir.join(format!("recovered_{:03}.{}", number, extension))
+}
+
+// Page files are kept next to the PDF unless --workdir says otherwise
+fn default_workdir(pdf_path: &str) -> PathBuf {
+    Path::new(pdf_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+// How page files are named: prefix + page number + suffix, "page007.txt" by default.
+// From the settings, for pipelines that write something like "p_007.out".
+#[derive(Clone)]
+struct PageFilePattern {
+    prefix: String,
+    suffix: String,
+}
+
+impl PageFilePattern {
+    fn of(settings: &Settings) -> Self {
+        Self {
+            prefix: settings.page_file_prefix.clone(),
+            suffix: settings.page_file_suffix.clone(),
+        }
+    }
+
+    // 1-based, zero padded to 3 digits like the files we write
+    fn file_name(&self, number: u16) -> String {
+        format!("{}{:03}{}", self.prefix, number, self.suffix)
+    }
+
+    // Counts as a page file even without a number (page.txt, page_old.txt), those are sorted last.
+    // Without a prefix only numbers count, or every .txt (decode_log.txt...) would be a page.
+    fn matches(&self, name: &str) -> bool {
+        match name.strip_prefix(&self.prefix).and_then(|rest| rest.strip_suffix(&self.suffix)) {
+            Some(middle) if self.prefix.is_empty() => {
+                !middle.is_empty() && middle.chars().all(|c| c.is_ascii_digit())
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    // "page007.txt" -> 7, None for anything that isn't prefix + digits + suffix
+    fn number_of(&self, path: &Path) -> Option<u16> {
+        let digits = path
+            .file_name()?
+            .to_str()?
+            .strip_prefix(&self.prefix)?
+            .strip_suffix(&self.suffix)?;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    // "pageNNN.txt", for the logs
+    fn describe(&self) -> String {
+        format!("{}NNN{}", self.prefix, self.suffix)
+    }
+}
+
+// The page files in the workdir, sorted by page number.
+// With `recursive` the subfolders are walked too, folder by folder in path order, so the
+// concatenation order doesn't depend on what the file system returns first.
+fn list_page_files(workdir: &Path, recursive: bool, pattern: &PageFilePattern) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![workdir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // file_type doesn't follow symlinks, so a link to a parent can't loop forever
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                if recursive {
+                    dirs.push(entry.path());
+                }
+            } else if pattern.matches(&name) {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    // Sort by number (page2 before page10, whatever the zero padding), files of the workdir
+    // itself come first, odd names like page.txt go last
+    files.sort_by_key(|path| {
+        (
+            path.parent().map(Path::to_path_buf),
+            pattern.number_of(path).unwrap_or(u16::MAX),
+            path.file_name().map(|name| name.to_os_string()),
+        )
+    });
+    files
+}
+
+// Window title, also names the directory eframe keeps its storage in
+const APP_NAME: &str = "PDF text to JPEG utility";
+
+// Keys in eframe's storage
+const LAST_FILE_KEY: &str = "last_file";
+const LAST_PAGE_KEY: &str = "last_page";
+
+// Written to the workdir by Export Combined
+const COMBINED_FILE_NAME: &str = "combined.txt";
+
+// Bookmarked pages of the document, one 1-based page number per line, next to its page files
+const BOOKMARKS_FILE_NAME: &str = "bookmarks.txt";
+
+const USAGE: &str = "Usage: cargo run -- [--headless] [--render-size <px>] [--autosave-secs <secs>] [--workdir <path>] [--password <password>] [--start-page <n>] [--recursive] [--decompress] [--display-script <path>] [<path_to_pdf>...]";
+
+struct CliArgs {
+    file_paths: Vec<String>, // One page range across all of them, empty reopens the last session
+    render_size: i32,   // Longest side of the rendered page, in pixels
+    autosave_secs: u64, // 0 disables autosave
+    workdir: Option<PathBuf>, // Where pageNNN.txt files live, defaults to the PDF's directory
+    headless: bool,           // Decode the page files and exit, no window
+    password: Option<String>, // For encrypted PDFs, otherwise the GUI asks for it
+    start_page: Option<u16>,  // 0-based, skips guessing from the saved page files
+    recursive: bool,          // Also look for page files in subfolders of the workdir
+    decompress: bool,         // Inflate a gzip/zlib stream before looking for images
+    display_script: Option<PathBuf>, // Run by the Display button instead of display_script.sh
+}
+
+impl CliArgs {
+    fn parse() -> Self {
+        let mut file_paths = Vec::new();
+        let mut render_size = 2000;
+        let mut autosave_secs = 30;
+        let mut workdir = None;
+        let mut headless = false;
+        let mut password = None;
+        let mut start_page = None;
+        let mut recursive = false;
+        let mut decompress = false;
+        let mut display_script = None;
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--render-size" => match args.next().and_then(|v| v.parse::<i32>().ok()) {
+                    Some(px) if px > 0 => render_size = px,
+                    _ => eprintln!("--render-size expects a positive number of pixels"),
+                },
+                "--autosave-secs" => match args.next().and_then(|v| v.parse::<u64>().ok()) {
+                    Some(secs) => autosave_secs = secs,
+                    None => eprintln!("--autosave-secs expects a number of seconds (0 to disable)"),
+                },
+                "--workdir" => match args.next() {
+                    Some(path) => workdir = Some(PathBuf::from(path)),
+                    None => eprintln!("--workdir expects a directory path"),
+                },
+                "--headless" => headless = true,
+                "--recursive" => recursive = true,
+                "--decompress" => decompress = true,
+                "--display-script" => match args.next() {
+                    Some(path) => display_script = Some(PathBuf::from(path)),
+                    None => eprintln!("--display-script expects the path of a script"),
+                },
+                "--password" => match args.next() {
+                    Some(value) => password = Some(value),
+                    None => eprintln!("--password expects the password of the PDF"),
+                },
+                // 1-based on the command line, like the page numbers in the UI
+                "--start-page" => match args.next().and_then(|v| v.parse::<u16>().ok()) {
+                    Some(page) if page > 0 => start_page = Some(page - 1),
+                    _ => eprintln!("--start-page expects a page number starting at 1"),
+                },
+                _ => file_paths.push(arg),
+            }
+        }
+
+        Self {
+            file_paths,
+            render_size,
+            autosave_secs,
+            workdir,
+            headless,
+            password,
+            start_page,
+            recursive,
+            decompress,
+            display_script,
+        }
+    }
+}
+
+// What the margin indicator of an editor line shows
+#[derive(Clone, Copy, PartialEq)]
+enum LineStatus {
+    Valid,                     // Exactly the configured number of Base64 characters
+    WrongLength(usize, usize), // Characters on the line, expected line length
+    InvalidChars(usize), // Number of characters that are not Base64
+    Lookalikes,          // Unicode homoglyphs of Base64 characters
+}
+
+impl LineStatus {
+    fn of(line: &str, alphabet: Base64Alphabet, line_length: usize) -> Self {
+        let char_count = line.trim().chars().count();
+        let invalid_count = line
+            .trim()
+            .chars()
+            .filter(|&c| !alphabet.valid_chars().contains(c))
+            .count();
+
+        if line.chars().any(decode::is_lookalike) {
+            LineStatus::Lookalikes
+        } else if invalid_count > 0 {
+            LineStatus::InvalidChars(invalid_count)
+        } else if char_count == line_length {
+            LineStatus::Valid
+        } else {
+            LineStatus::WrongLength(char_count, line_length)
+        }
+    }
+
+    // Colors are picked to stand out on the theme's background
+    fn color(self, dark_mode: bool) -> egui::Color32 {
+        match self {
+            LineStatus::Valid if dark_mode => egui::Color32::GREEN,
+            LineStatus::Valid => egui::Color32::DARK_GREEN,
+            LineStatus::WrongLength(char_count, line_length) => {
+                // Near misses (likely one char inserted or lost) stand out the most,
+                // anything 6 or more chars off fades into the background
+                let deviation = char_count.abs_diff(line_length).min(6) as u8;
+                if dark_mode {
+                    egui::Color32::from_gray(230 - (deviation - 1) * 36)
+                } else {
+                    egui::Color32::from_gray(40 + (deviation - 1) * 36)
+                }
+            }
+            LineStatus::InvalidChars(_) if dark_mode => egui::Color32::ORANGE,
+            LineStatus::InvalidChars(_) => egui::Color32::from_rgb(220, 110, 0),
+            LineStatus::Lookalikes => egui::Color32::from_rgb(160, 32, 240), // Purple
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            LineStatus::Valid => "OK".to_string(),
+            LineStatus::WrongLength(char_count, line_length) => {
+                format!("{} characters (expected {})", char_count, line_length)
+            }
+            LineStatus::InvalidChars(count) => format!("{} invalid characters", count),
+            LineStatus::Lookalikes => "Unicode lookalikes".to_string(),
+        }
+    }
+}
+
+// How many lines of a page have the configured length, a segment of the validity minimap
+#[derive(Clone, Copy)]
+struct PageValidity {
+    valid_lines: usize,
+    lines: usize, // Blank lines don't count
+}
+
+impl PageValidity {
+    fn of(text: &str, alphabet: Base64Alphabet, line_length: usize) -> Self {
+        let mut validity = PageValidity { valid_lines: 0, lines: 0 };
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            validity.lines += 1;
+            if LineStatus::of(line, alphabet, line_length) == LineStatus::Valid {
+                validity.valid_lines += 1;
+            }
+        }
+        validity
+    }
+
+    // Red for a page without a single good line, green when all of them are
+    fn color(self) -> egui::Color32 {
+        if self.lines == 0 {
+            return egui::Color32::GRAY;
+        }
+        let fraction = self.valid_lines as f32 / self.lines as f32;
+        egui::Color32::RED.lerp_to_gamma(egui::Color32::GREEN, fraction)
+    }
+
+    fn describe(self) -> String {
+        if self.lines == 0 {
+            return "no text".to_string();
+        }
+        format!(
+            "{} of {} lines valid ({:.0}%)",
+            self.valid_lines,
+            self.lines,
+            100.0 * self.valid_lines as f32 / self.lines as f32
+        )
+    }
+}
+
+// How many rendered pages we keep on the GPU
+const PAGE_TEXTURE_CACHE_SIZE: usize = 16;
+
+// Ctrl+click highlights cycle through these so neighbouring ranges can be told apart
+const PINNED_HIGHLIGHT_PALETTE: [egui::Color32; 5] = [
+    egui::Color32::from_rgb(255, 140, 0),
+    egui::Color32::from_rgb(255, 0, 255),
+    egui::Color32::from_rgb(0, 200, 255),
+    egui::Color32::from_rgb(255, 220, 0),
+    egui::Color32::from_rgb(255, 60, 60),
+];
+
+// Longest side of a page thumbnail, in pixels
+const THUMBNAIL_SIZE: i32 = 120;
+
+// Height of the recovered images in the results list, click one for the full size
+const RECOVERED_THUMBNAIL_HEIGHT: f32 = 200.0;
+
+const MINIMAP_WIDTH: f32 = 24.0;
+
+// How long the "Saved page · decoded" toast stays, it fades out over the last TOAST_FADE
+const TOAST_DURATION: Duration = Duration::from_millis(2500);
+const TOAST_FADE: Duration = Duration::from_millis(500);
+
+// Editor font sizes the A-/A+ buttons and Ctrl+scroll stay within
+const EDITOR_FONT_SIZE_RANGE: std::ops::RangeInclusive<f32> = 8.0..=72.0;
+
+// Seconds since the epoch as "2024-01-31 23:59:59 UTC"
+fn format_utc(secs: u64) -> String {
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let time = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
     )
 }
 
-const BASE64_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/= ";
+// 1482204 -> "1,482,204"
+fn format_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+// How many undo snapshots we keep for the current page
+const UNDO_HISTORY_SIZE: usize = 500;
+
+// Everything the Shortcuts window (F1) lists, keep it in sync with the key handling in update()
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("Ctrl+S", "Save the page and decode"),
+    ("Ctrl+Shift+S", "Save all pages"),
+    ("F5", "Reload the page file from disk"),
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Y / Ctrl+Shift+Z", "Redo"),
+    ("Ctrl+J", "Jump to the next ambiguous character (I/l/1 by default)"),
+    ("Ctrl+Shift+J", "Jump to the previous ambiguous character"),
+    ("Ctrl+L", "Select the cursor line, to see all of it on the page"),
+    ("Ctrl+Shift+L", "Jump to the next line of the wrong length"),
+    ("Ctrl+G", "Jump to a hex offset of the decoded stream"),
+    ("Ctrl+B", "Bookmark the page for later review (again to remove it)"),
+    ("F2 / Shift+F2", "Next / previous bookmarked page"),
+    ("Ctrl+F", "Find in the page, Enter / Shift+Enter for the next / previous match"),
+    ("Ctrl+H", "Find and replace"),
+    ("Ctrl+P", "Focus the \"Go to page\" input"),
+    ("Ctrl+D", "Run the display script"),
+    ("Ctrl+Space", "Cycle the next character through common OCR mistakes"),
+    ("Ctrl+I", "Insert a placeholder for a dropped character (listed in Placeholders)"),
+    ("Ctrl+Enter", "Finalize the line, align its trailing spaces with the PDF"),
+    ("Ctrl+Click / Ctrl+Drag", "Keep the selection highlighted on the page"),
+    ("PageUp / PageDown, Left / Right", "Previous / next page (no text field focused)"),
+    ("Home / End", "First / last page (no text field focused)"),
+    ("Ctrl+Scroll", "Zoom the page or a recovered image, over the editor change its font size"),
+    ("F1", "Show this list"),
+];
+
+// What the decoding thread sends back to the UI
+enum DecodeMessage {
+    Log(String),
+    Progress(f32), // 0.0 to 1.0
+    DecodedBytes(Vec<u8>), // The decoded stream (per-page mode: the stream of one page)
+    Truncated, // A segment without an end marker was found
+    Source(String), // Following images come from this page file (per-page mode)
+    Image {
+        color_image: egui::ColorImage, // Ready to be uploaded as a texture
+        bytes: Vec<u8>,
+        format: Option<image::ImageFormat>,
+        offset: usize, // Where the image starts in the decoded stream
+        partial: Option<PartialImage>, // Only some rows could be decoded
+    },
+    CharSearch(CharSearch), // Every value tried for one char, see run_char_search
+}
+
+// Every value tried for one unknown char of a page, for the Placeholders window
+struct CharSearch {
+    page: u16,
+    position: usize,            // Char index in the page text
+    ranked: Vec<CharCandidate>, // Best first
+}
+
+struct CharCandidate {
+    value: char,
+    complete_images: usize, // Images of the stream that decode completely with this value
+    jpeg_rows: usize,       // JPEG rows a strict decode gets through
+}
+
+// How much of a JPEG survived, for the badge in the results panel
+#[derive(Clone, Copy)]
+struct PartialImage {
+    truncated: bool, // The stream ended before the image did, otherwise the data is corrupt
+    decoded_fraction: f32,
+}
+
+// What the top of the results panel shows
+#[derive(PartialEq)]
+enum ResultsTab {
+    Stream,
+    Scratch,
+}
+
+// An image recovered from the decoded stream
+struct RecoveredImage {
+    texture: Option<egui::TextureHandle>, // None once freed to stay under settings.max_image_textures
+    size: [usize; 2],
+    offset: usize,
+    bytes: Vec<u8>, // Original decoded bytes, saved as-is
+    format: Option<image::ImageFormat>,
+    source: Option<String>, // Page file it was decoded from, per-page mode only
+    metadata: Vec<(&'static str, String)>, // EXIF fields, empty if there are none
+    partial: Option<PartialImage>,
+}
+
+impl RecoveredImage {
+    // The scanner doesn't know every format, the image crate can still tell from the bytes
+    fn format_or_guess(&self) -> Option<image::ImageFormat> {
+        self.format.or_else(|| image::guess_format(&self.bytes).ok())
+    }
+
+    // The results panel filter, the image itself is kept either way
+    fn is_shown(&self, min_size: usize, format: Option<image::ImageFormat>) -> bool {
+        self.size[0] >= min_size
+            && self.size[1] >= min_size
+            && format.is_none_or(|format| self.format_or_guess() == Some(format))
+    }
+}
 
 struct PdfApp {
     // PDF State
-    document: Option<PdfDocument<'static>>,
+    documents: Vec<PdfDocument<'static>>, // Their pages form one range, in this order
+    document_paths: Vec<String>,         // Remembered for the next session
     current_page_index: u16,
-    total_pages: u16,
+    total_pages: u16, // Of all documents together
 
     // Visual State
+    pdf_view_fraction: f32, // Share of the window height for the PDF view
+    editor_fraction: f32,   // Share of the window height for the editor
     page_texture: Option<egui::TextureHandle>,
     page_size: egui::Vec2,
+    zoom: f32,       // PDF view zoom, 1.0 = fit width
+    pan: egui::Vec2, // Offset of the zoomed image inside the PDF view
+    page_texture_cache: HashMap<u16, egui::TextureHandle>,
+    page_texture_lru: VecDeque<u16>, // Most recently used at the back
+    thumbnail_cache: HashMap<u16, egui::TextureHandle>,
+    page_validity: HashMap<u16, PageValidity>, // Minimap stats of the pages, the current one is always fresh
+    thumbnails_requested: HashSet<u16>, // Asked of the page thread, not in thumbnail_cache yet
+    validity_requested: HashSet<u16>,   // Same for page_validity
 
     // Text State
     text_content: String,
+    pdf_text: String, // As extracted from the PDF, text_content may come from the saved file instead
+    text_from_file: bool, // text_content was loaded from (or since saved to) the page file, not the PDF text
+
+    // Undo/Redo State, reset when a different page is loaded
+    undo_stack: Vec<String>,
+    redo_stack: Vec<String>,
+    history_snapshot: String, // Last known text_content, pushed onto undo_stack on change
+
+    // Autosave State
+    text_dirty: bool, // text_content changed since the last write to disk
+    autosave_interval: Option<Duration>,
+    last_save: Instant,
+    last_autosave: Option<Instant>, // For the "saved" indicator in the toolbar
 
-    _pdfium: &'static Pdfium,
+    pdfium: Option<&'static Pdfium>, // None when the library could not be loaded
+    render_size: i32,
+    page_tx: Option<mpsc::Sender<PageRequest>>, // To the page thread, None without the library
+    page_rx: Option<mpsc::Receiver<PageResult>>,
+    render_generation: u64, // Bumped on every page change, renders of abandoned pages are dropped
+    overview_generation: u64, // Bumped when the thumbnails or the minimap stats are thrown away
+    rendering_page: Option<u16>, // Waiting for its bitmap, the PDF view shows a placeholder meanwhile
+    workdir: PathBuf,
+    workdir_is_explicit: bool, // Given with --workdir, so opening another PDF keeps it
+    pdf_password: Option<String>, // Tried when loading the PDF, from --password or the prompt
+    password_prompt: Option<(Vec<String>, Option<u16>)>, // (paths, start_page) waiting for a password
+    password_input: String,
 
-    decoded_textures: Vec<egui::TextureHandle>, // Stores the recovered JPEGs
+    alphabet: Base64Alphabet,
+    cleaning_filter: CleaningFilter,
+    per_page_decode: bool, // Every pageNNN.txt is a self-contained Base64 blob
+    image_min_size: usize, // Hide recovered images narrower or lower than this, 0 shows all
+    image_format_filter: Option<image::ImageFormat>, // Only list images of this format, None for all
+    recursive_scan: bool,  // Also collect page files from subfolders of the workdir
+    decompress_stream: bool, // Inflate the decoded bytes first when they are gzip/zlib
+    try_alignments: bool,    // Also decode with 1-3 leading chars skipped, keep the best
+    combined_one_line: bool, // Export Combined without line breaks
+    decode_source: Option<String>, // Where the images being received come from
+    recovered_images: Vec<RecoveredImage>, // Stores the recovered JPEGs
+    viewed_image: Option<usize>,           // Index in recovered_images shown at full size
+    viewed_image_zoom: f32,
     decode_logs: Vec<String>,                   // Stores status reports
+    decode_rx: Option<mpsc::Receiver<DecodeMessage>>, // Decoding in progress
+    decode_progress: f32,                       // 0.0 to 1.0
+    decoded_bytes: Vec<u8>,                     // The last decoded stream, for the hex dump
+    decoded_magic: Vec<(usize, usize)>,         // (offset, length) of image signatures in it
+    truncated_segment_count: usize,
+    results_tab: ResultsTab,
+    scratch_text: String, // Pasted Base64, decoded independently of the page files
 
     show_hex_dialog: bool,
     hex_input: String,
+    hex_jump_decimal: bool, // The offset in hex_input is decimal
     jump_status_msg: String,
+
+    goto_page_input: String,
+    bookmarks: HashSet<u16>, // Pages flagged for review, kept in bookmarks.txt of the workdir
+    goto_page_msg: String,
+
+    highlight_all_matches: bool, // Underline every occurrence of the selection on the page
+    show_problem_lines: bool,    // List of the lines the margin indicators don't show green
+    show_pdf_text: bool,         // Window comparing the PDF text with the editor
+    pdf_text_raw: bool,          // That window shows the PDF text as is instead of the diff
+    show_page_diff: bool,        // Window comparing the editor with another page
+    compare_page_index: u16,
+    compare_page_text: String,   // Of compare_page_index, read when the window opens or the page changes
+    show_histogram: bool,        // Window with the frequency of every Base64 char
+    show_placeholders: bool,     // Window listing the placeholders of the page
+    placeholders: Vec<usize>,    // Char indices of the Ctrl+I placeholders on the current page, sorted
+    placeholder_snapshot: String, // text_content the placeholders point into, to follow the edits
+    char_search: Option<CharSearch>, // Last run of Try All, ranked
+    histogram_whole_document: bool, // Count all saved page files instead of the editor
+    document_histogram: Vec<(char, usize)>, // Counted when the document is picked or on Recount
+
+    show_find_bar: bool,
+    focus_find_bar: bool, // Ctrl+F was pressed, focus the input on the next frame
+    find_bar_query: String,
+    find_bar_current: Option<usize>, // Index of the selected match in find_matches()
+    scroll_to_cursor: bool,          // Bring the editor cursor into view even without focus
+
+    side_by_side: bool,               // PDF view left of the editor instead of above it
+    synced_top_line: Option<usize>,   // Editor line the PDF view was last scrolled to
+    pdf_sync_fraction: Option<f32>,   // Pending PDF view scroll, 0..1 of the page height
+
+    status_msg: String, // Short feedback shown in the toolbar
+    toast: Option<(String, Instant)>, // Corner notification and when it appeared
+    toast_after_decode: Option<String>, // "Saved page 12", shown with the image count once the decode is done
+
+    base64_before_page: u64, // Base64 chars in the saved files of earlier pages
+
+    settings: Settings,
+    show_settings: bool,
+    display_script: Option<PathBuf>, // --display-script, wins over the one in Settings
+    script_rx: Option<mpsc::Receiver<String>>, // Output of the running display script
+    show_shortcuts: bool,
+    ambiguous_chars_input: String, // Edit buffer for settings.ambiguous_chars
+
+    show_save_all_dialog: bool,
+    save_all_conflicts: Vec<u16>, // Pages whose files have manual edits
+
+    pinned_selections: Vec<egui::text::CCursorRange>, // Ctrl+click ranges, highlighted next to the selection
+    show_reload_dialog: bool, // Reloading would throw away unsaved edits
+    pending_page: Option<u16>, // Waiting for save/discard of the unsaved edits before we go there
+
+    show_replace_dialog: bool,
+    find_input: String,
+    replace_input: String,
+    replace_whole_word: bool,
+    replace_all_pages: bool, // Also rewrite the saved files of the other pages
+    replace_status_msg: String,
 }
 
 impl PdfApp {
-    fn new(cc: &eframe::CreationContext<'_>, pdfium: &'static Pdfium, path: String) -> Self {
+    fn new(
+        cc: &eframe::CreationContext<'_>,
+        pdfium: Result<&'static Pdfium, String>,
+        args: CliArgs,
+    ) -> Self {
+        // Without files on the command line, reopen the ones from the last session
+        let mut start_page = None;
+        let mut paths = args.file_paths.clone();
+        if paths.is_empty() {
+            if let Some(last_files) = cc.storage.and_then(|storage| storage.get_string(LAST_FILE_KEY)) {
+                let last_files: Vec<String> = last_files.lines().map(str::to_string).collect();
+                match last_files.iter().find(|path| !Path::new(path).exists()) {
+                    Some(missing) => eprintln!("Last opened file {} no longer exists", missing),
+                    None => {
+                        start_page = cc
+                            .storage
+                            .and_then(|storage| storage.get_string(LAST_PAGE_KEY))
+                            .and_then(|p| p.parse::<u16>().ok());
+                        paths = last_files;
+                    }
+                }
+            }
+        }
+        // --start-page wins over both the last session and the saved page files
+        if args.start_page.is_some() {
+            start_page = args.start_page;
+        }
+
+        let (page_tx, page_rx) = pdfium
+            .as_ref()
+            .ok()
+            .map(|&pdfium| spawn_page_thread(pdfium, cc.egui_ctx.clone()))
+            .unzip();
+
         let mut app = Self {
-            document: None,
+            documents: Vec::new(),
+            document_paths: Vec::new(),
             current_page_index: 0,
             total_pages: 0,
+            pdf_view_fraction: 0.25,
+            editor_fraction: 0.35,
             page_texture: None,
+            page_validity: HashMap::new(),
             page_size: egui::Vec2::ZERO,
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            page_texture_cache: HashMap::new(),
+            page_texture_lru: VecDeque::new(),
+            thumbnail_cache: HashMap::new(),
+            thumbnails_requested: HashSet::new(),
+            validity_requested: HashSet::new(),
             text_content: String::new(),
-            _pdfium: pdfium,
-            decoded_textures: Vec::new(),
+            pdf_text: String::new(),
+            text_from_file: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            history_snapshot: String::new(),
+            text_dirty: false,
+            autosave_interval: (args.autosave_secs > 0)
+                .then(|| Duration::from_secs(args.autosave_secs)),
+            last_save: Instant::now(),
+            last_autosave: None,
+            pdfium: pdfium.as_ref().ok().copied(),
+            render_size: args.render_size,
+            page_tx,
+            page_rx,
+            render_generation: 0,
+            overview_generation: 0,
+            rendering_page: None,
+            workdir: args
+                .workdir
+                .clone()
+                .unwrap_or_else(|| default_workdir(paths.first().map(String::as_str).unwrap_or("."))),
+            workdir_is_explicit: args.workdir.is_some(),
+            pdf_password: args.password.clone(),
+            password_prompt: None,
+            password_input: String::new(),
+            alphabet: Base64Alphabet::Standard,
+            cleaning_filter: CleaningFilter::default(),
+            per_page_decode: false,
+            image_min_size: 0,
+            image_format_filter: None,
+            recursive_scan: args.recursive,
+            decompress_stream: args.decompress,
+            try_alignments: false,
+            combined_one_line: false,
+            decode_source: None,
+            recovered_images: Vec::new(),
+            viewed_image: None,
+            viewed_image_zoom: 1.0,
             decode_logs: Vec::new(),
+            decode_rx: None,
+            decode_progress: 0.0,
+            decoded_bytes: Vec::new(),
+            decoded_magic: Vec::new(),
+            truncated_segment_count: 0,
+            results_tab: ResultsTab::Stream,
+            scratch_text: String::new(),
             show_hex_dialog: false,
             hex_input: String::new(),
+            hex_jump_decimal: false,
             jump_status_msg: String::new(),
+            goto_page_input: String::new(),
+            bookmarks: HashSet::new(),
+            goto_page_msg: String::new(),
+            highlight_all_matches: false,
+            show_problem_lines: false,
+            show_pdf_text: false,
+            pdf_text_raw: false,
+            show_page_diff: false,
+            show_histogram: false,
+            show_placeholders: false,
+            placeholders: Vec::new(),
+            placeholder_snapshot: String::new(),
+            char_search: None,
+            histogram_whole_document: false,
+            document_histogram: Vec::new(),
+            compare_page_index: 0,
+            compare_page_text: String::new(),
+            show_find_bar: false,
+            focus_find_bar: false,
+            find_bar_query: String::new(),
+            find_bar_current: None,
+            scroll_to_cursor: false,
+            side_by_side: false,
+            synced_top_line: None,
+            pdf_sync_fraction: None,
+            status_msg: String::new(),
+            toast: None,
+            toast_after_decode: None,
+            base64_before_page: 0,
+            settings: Settings::load(),
+            show_settings: false,
+            display_script: args.display_script.clone(),
+            script_rx: None,
+            show_shortcuts: false,
+            ambiguous_chars_input: String::new(),
+            show_save_all_dialog: false,
+            save_all_conflicts: Vec::new(),
+            pinned_selections: Vec::new(),
+            show_reload_dialog: false,
+            pending_page: None,
+            show_replace_dialog: false,
+            find_input: String::new(),
+            replace_input: String::new(),
+            replace_whole_word: false,
+            replace_all_pages: false,
+            replace_status_msg: String::new(),
         };
 
-        if let Ok(doc) = pdfium.load_pdf_from_file(&path, None) {
-            app.total_pages = doc.pages().len();
-            app.document = Some(doc);
-            app.load_page(&cc.egui_ctx, Self::latest_index());
-        } else {
-            app.text_content = format!("Could not load PDF at path: {}", path);
+        app.apply_theme(&cc.egui_ctx);
+
+        match pdfium {
+            Err(msg) => {
+                // Degraded mode, only the Scratch decoder is useful
+                app.text_content = msg;
+                app.results_tab = ResultsTab::Scratch;
+            }
+            Ok(_) if !paths.is_empty() => app.open_documents(&paths, start_page),
+            Ok(_) => {
+                eprintln!("{}", USAGE);
+                app.text_content = USAGE.to_string();
+            }
         }
 
         app
     }
 
-    fn latest_index() -> u16 {
-        let mut max_index = 0;
-    
-        if let Ok(entries) = fs::read_dir(".") {
-            for entry in entries.flatten() {
-                if let Some(file_name) = entry.file_name().to_str() {
-                    if let Some(captures) = file_name.strip_prefix("page").and_then(|s| s.strip_suffix(".txt")) {
-                        if let Ok(index) = captures.parse::<u16>() {
-                            if index > max_index {
-                                max_index = index - 1;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    
-        max_index
+    fn apply_theme(&self, ctx: &egui::Context) {
+        ctx.set_theme(if self.settings.dark_mode {
+            egui::Theme::Dark
+        } else {
+            egui::Theme::Light
+        });
     }
 
-    fn load_page(&mut self, ctx: &egui::Context, index: u16) {
-        if let Some(doc) = &self.document {
-            if let Ok(page) = doc.pages().get(index) {
-                // 1. Render Page to Bitmap
-                let bitmap = page.render(2000, 2000, None).unwrap();
-                let image = bitmap.as_image();
-                let size = [image.width() as usize, image.height() as usize];
-                let pixels = image.into_rgb8();
+    // Loads the PDFs as one continuous range of pages, dropping everything that belonged to
+    // the previous ones. start_page overrides the guess from the saved page files
+    fn open_documents(&mut self, paths: &[String], start_page: Option<u16>) {
+        self.documents.clear();
+        self.document_paths.clear();
+        self.total_pages = 0;
+        self.current_page_index = 0;
+        self.page_texture = None;
+        self.page_texture_cache.clear();
+        self.page_texture_lru.clear();
+        self.clear_thumbnails();
+        self.clear_page_validity();
+        self.rendering_page = None;
+        self.zoom = 1.0;
+        self.pan = egui::Vec2::ZERO;
+        self.recovered_images.clear();
+        self.viewed_image = None;
+        self.decode_logs.clear();
+        self.decode_rx = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.text_content.clear();
+        self.pdf_text.clear();
+        self.bookmarks.clear();
+        // Char positions into the text of the previous documents, load_page keeps them on the same page index
+        self.pinned_selections.clear();
+        self.placeholders.clear();
+        self.placeholder_snapshot.clear();
+        self.char_search = None;
 
-                // 2. Upload to Egui GPU Texture
-                let color_image = egui::ColorImage::from_rgb(size, &pixels);
-                self.page_texture =
-                    Some(ctx.load_texture("pdf_page", color_image, egui::TextureOptions::LINEAR));
+        let Some(first_path) = paths.first() else {
+            return;
+        };
+        if !self.workdir_is_explicit {
+            // Page files of all documents live together, numbered across them
+            self.workdir = default_workdir(first_path);
+        }
 
-                self.page_size = egui::vec2(page.width().value, page.height().value);
+        let Some(pdfium) = self.pdfium else {
+            self.text_content = format!(
+                "Can't open {}, the PDFium library is not loaded. Place {} in the current directory and restart.",
+                paths.join(", "),
+                Pdfium::pdfium_platform_library_name().to_string_lossy()
+            );
+            return;
+        };
 
-                // 3. Extract Text
-                if let Ok(text) = page.text() {
-                    self.text_content = text.all();
+        let mut documents = Vec::new();
+        for path in paths {
+            match pdfium.load_pdf_from_file(path, self.pdf_password.as_deref()) {
+                Ok(doc) => documents.push(doc),
+                Err(PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError)) => {
+                    self.text_content = if self.pdf_password.is_some() {
+                        format!("Wrong password for PDF at path: {}", path)
+                    } else {
+                        format!("PDF at path {} is password protected", path)
+                    };
+                    // Ask for it, the dialog calls us again
+                    self.password_prompt = Some((paths.to_vec(), start_page));
+                    self.password_input.clear();
+                    return;
                 }
-
-                // 4. If the file exists, load its text
-                let file_name = format!("page{:03}.txt", index + 1);
-                if let Ok(content) = std::fs::read_to_string(&file_name) {
-                    eprintln!("Loading file {}", file_name);
-                    self.text_content = content;
+                Err(PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::FileError)) => {
+                    self.text_content = format!("PDF not found or not readable at path: {}", path);
+                    return;
+                }
+                Err(e) => {
+                    self.text_content = format!("Could not load PDF at path: {} ({:?})", path, e);
+                    return;
                 }
-
-                // Replace any 0x0D character with spaces
-                self.text_content = self
-                    .text_content
-                    .chars()
-                    .map(|c| if c == '\u{0D}' { ' ' } else { c })
-                    .collect();
-
-                self.current_page_index = index;
             }
         }
+
+        // Pages are numbered across the documents with a u16, like within one of them
+        let Some(total_pages) = documents.iter().try_fold(0u16, |total, doc| total.checked_add(doc.pages().len()))
+        else {
+            self.text_content = format!(
+                "{} have more than {} pages together, open fewer of them at once",
+                paths.join(", "),
+                u16::MAX
+            );
+            return;
+        };
+        self.total_pages = total_pages;
+        self.documents = documents;
+        self.document_paths = paths.to_vec();
+        let start_index = match start_page {
+            Some(page) if page < self.total_pages => page,
+            Some(page) => {
+                eprintln!("Start page {} is past the last page {}", page + 1, self.total_pages);
+                Self::latest_index(&self.workdir, &PageFilePattern::of(&self.settings))
+            }
+            None => Self::latest_index(&self.workdir, &PageFilePattern::of(&self.settings)),
+        };
+        self.load_bookmarks();
+        self.load_page(start_index);
     }
 
-    fn get_highlights(&self, selection: egui::text::CCursorRange) -> Vec<egui::Rect> {
-        let mut rects = Vec::new();
+    fn open_document_dialog(&mut self) {
+        if let Some(paths) = rfd::FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .pick_files()
+        {
+            // A password belongs to the files it was given for
+            self.pdf_password = None;
+            let paths: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+            self.open_documents(&paths, None);
+        }
+    }
 
-        if let Some(doc) = &self.document {
-            if let Ok(page) = doc.pages().get(self.current_page_index) {
-                let boundaries = page.boundaries();
-                let crop = boundaries
-                    .crop()
-                    .unwrap_or(boundaries.media().expect("Neither crop no media present"));
-
-                let p_width = crop.bounds.width().value;
-                let p_height = crop.bounds.height().value;
-                let p_left_offset = crop.bounds.left().value;
-                let _p_bottom_offset = crop.bounds.bottom().value;
-                // In PDF, 'top' is the highest Y value.
-                // We use this to flip the Y-axis.
-                let p_top_value = crop.bounds.top().value;
-
-                if let Ok(text_page) = page.text() {
-                    // Egui gives us Byte Indices
-                    let start_byte = selection.primary.index.min(selection.secondary.index);
-                    let end_byte = selection.primary.index.max(selection.secondary.index);
-
-                    // Conversion: Byte Index -> Char Index
-                    if start_byte < self.text_content.len() + 1 {
-                        let start_char_idx = self.text_content[..start_byte].chars().count();
-
-                        let char_count = if start_byte == end_byte {
-                            1
-                        } else {
-                            self.text_content[start_byte..end_byte].chars().count()
-                        };
+    // Appends PDFs after the last page, keeping the current page and its edits
+    fn add_document_dialog(&mut self) {
+        let Some(paths) = rfd::FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .pick_files()
+        else {
+            return;
+        };
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        let Some(pdfium) = self.pdfium.filter(|_| !self.documents.is_empty()) else {
+            self.open_documents(&paths, None);
+            return;
+        };
 
-                        for char_obj in text_page
-                            .chars()
-                            .iter()
-                            .skip(start_char_idx)
-                            .take(char_count)
-                        {
-                            if let Ok(rect) = char_obj.loose_bounds() {
-                                // We calculate coordinates RELATIVE to the page dimensions (0.0 to 1.0)
-                                // This helps if the rendered image has been cropped or scaled differently.
-                                let left_pct = (rect.left().value - p_left_offset) / p_width;
-                                let top_pct = (p_top_value - rect.top().value) / p_height;
-                                let width_pct = (rect.right().value - rect.left().value) / p_width;
-                                let height_pct =
-                                    (rect.top().value - rect.bottom().value) / p_height;
-
-                                rects.push(egui::Rect::from_min_size(
-                                    egui::pos2(left_pct, top_pct),
-                                    egui::vec2(width_pct, height_pct),
-                                ));
-                            }
-                        }
-                    }
+        for path in paths {
+            match pdfium.load_pdf_from_file(&path, self.pdf_password.as_deref()) {
+                Ok(doc) => {
+                    self.total_pages += doc.pages().len();
+                    self.documents.push(doc);
+                    self.status_msg = format!("Added {}, {} pages in total", path, self.total_pages);
+                    self.document_paths.push(path);
                 }
+                Err(e) => self.status_msg = format!("Could not add {}: {:?}", path, e),
             }
         }
-        rects
     }
 
-    fn save_page(&self) {
-        let filename = format!("page{:03}.txt", self.current_page_index + 1);
-
-        if let Err(e) = fs::write(&filename, &self.text_content) {
-            eprintln!("Error saving file {}: {}", filename, e);
-        } else {
-            println!("Saved text to {}", filename);
+    // Which document a page of the combined range is in: (document, page in that document)
+    fn locate_page(documents: &[PdfDocument<'static>], index: u16) -> Option<(usize, u16)> {
+        let mut local = index;
+        for (i, doc) in documents.iter().enumerate() {
+            let count = doc.pages().len();
+            if local < count {
+                return Some((i, local));
+            }
+            local -= count;
         }
+        None
     }
 
-    fn jump_to_ilone(&self, ctx: &egui::Context) {
-        let text_id = egui::Id::new("shared_pdf_editor_id");
-                    
-        // 1. Get current cursor position (default to 0 if not set)
-        let current_idx = if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-             state.cursor.char_range().map(|r| r.primary.index).unwrap_or(0)
-        } else {
-            0
-        };
+    // Takes the documents rather than &self, so the other fields stay free to change while a page is open
+    fn document_page(documents: &[PdfDocument<'static>], index: u16) -> Option<PdfPage<'_>> {
+        let (doc, local) = Self::locate_page(documents, index)?;
+        documents[doc].pages().get(local).ok()
+    }
 
-        // 2. Search for next char starting AFTER current cursor
-        // We define the set of characters to look for
-        let targets = ['I', 'l', '1'];
-        
-        // Slice the string from current_idx + 1 to end
-        if current_idx + 1 < self.text_content.len() {
-            let slice = &self.text_content[current_idx + 1..];
-            
-            // Find the offset within the slice
-            if let Some(offset) = slice.find(&targets[..]) {
-                let new_index = current_idx + 1 + offset;
-                
-                // 3. Mutate the TextEdit State
-                if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-                    // Set cursor to the new index
-                    state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
-                        egui::text::CCursor::new(new_index)
-                    )));
-                    
-                    // Save the state back
-                    state.store(ctx, text_id);
-                    
-                    // 4. Focus the editor so user can type immediately
-                    ctx.memory_mut(|m| m.request_focus(text_id));
+    // The page we were working on last time: the highest numbered page file
+    fn latest_index(workdir: &Path, pattern: &PageFilePattern) -> u16 {
+        // 1-based page number, as in the file names
+        let mut max_page = 0;
+        let mut found = 0;
+    
+        if let Ok(entries) = fs::read_dir(workdir) {
+            for entry in entries.flatten() {
+                if let Some(page) = pattern.number_of(&entry.path()) {
+                    max_page = max_page.max(page);
+                    found += 1;
                 }
             }
         }
+    
+        // Pages are 0-indexed, no files (or only page000.txt) means the first page
+        let index = max_page.saturating_sub(1);
+        eprintln!(
+            "Found {} {} file(s) in {}, starting at page {} (use --start-page to override)",
+            found,
+            pattern.describe(),
+            workdir.display(),
+            index + 1
+        );
+        index
     }
 
-    // CORE LOGIC: Load files -> Clean -> Base64 -> Scan for JPEGs
-    fn run_stream_decoding(&mut self, ctx: &egui::Context) {
-        use base64::{Engine as _,};
-
-        self.decoded_textures.clear();
-        self.decode_logs.clear();
-
-        // 1. Load and Sort Files
-        self.decode_logs.push("Scanning current directory for page*.txt...".to_owned());
-        let mut file_contents = Vec::new();
-        let mut count = 0;
-        
-        if let Ok(entries) = fs::read_dir(".") {
-            let mut files: Vec<_> = entries.flatten()
-                .filter(|e| {
-                    e.file_name().to_string_lossy().starts_with("page") 
-                    && e.file_name().to_string_lossy().ends_with(".txt")
-                })
-                .collect();
+    fn load_page(&mut self, index: u16) {
+        if let Some(page) = Self::document_page(&self.documents, index) {
+            // 1. Reuse the cached texture, or render Page to Bitmap on the page thread
+            // (the texture is uploaded in poll_page_thread when it arrives)
+            if let Some(texture) = self.page_texture_cache.get(&index) {
+                self.page_texture = Some(texture.clone());
+                // A render still running for the page we left is of no use anymore
+                self.render_generation += 1;
+                self.rendering_page = None;
+                Self::touch_page_texture(
+                    &mut self.page_texture_lru,
+                    &mut self.page_texture_cache,
+                    index,
+                );
+            } else {
+                self.request_render(index);
+            }
 
-            // Sort by number (page001, page002)
-            files.sort_by_key(|e| {
-                let name = e.file_name().to_string_lossy().to_string();
-                let num_str = &name[4..name.len()-4]; // strip "page" and ".txt"
-                num_str.parse::<u32>().unwrap_or(9999)
-            });
+            self.page_size = egui::vec2(page.width().value, page.height().value);
 
-            for file in files {
-                if let Ok(content) = fs::read_to_string(file.path()) {
-                    // self.decode_logs.push(format!("Loaded: {:?}", file.file_name()));
-                    file_contents.push(content);
-                    count += 1;
+            // 3. Extract Text, while the page renders
+            // (on failure the editor is emptied, the previous page's text must not stay around)
+            match page.text() {
+                Ok(text) => self.text_content = text.all(),
+                Err(e) => {
+                    let msg = format!("Could not extract the text of page {}: {:?}", index + 1, e);
+                    eprintln!("{}", msg);
+                    self.status_msg = msg.clone();
+                    self.decode_logs.push(msg);
+                    self.text_content.clear();
                 }
             }
-        }
-        self.decode_logs.push(format!("Loaded {} files", count));
+            // Kept to compare the saved corrections with (PDF Text window)
+            self.pdf_text = self.clean_page_text(&self.text_content);
 
-        let raw_string = file_contents.join("");
-        self.decode_logs.push(format!("Total raw length: {} characters", raw_string.len()));
-
-        // 2. Clean Base64 Stream
-        // We strip everything that isn't a Base64 data char (A-Z, a-z, 0-9, +, /).
-        // We explicitly REMOVE existing '=' padding. The permissive decoder will 
-        // handle the necessary padding logic internally.
-        let clean_string: String = raw_string.chars()
-            .filter(|c| c.is_alphanumeric() || *c == '+' || *c == '/')
-            .collect();
+            // 4. If the file exists, load its text
+            let file_name = self.page_file_path(index);
+            self.text_from_file = false;
+            if let Ok(content) = std::fs::read_to_string(&file_name) {
+                eprintln!("Loading file {}", file_name.display());
+                self.text_content = content;
+                self.text_from_file = true;
+            }
 
-        self.decode_logs.push(format!("Cleaned Base64 length: {} characters", clean_string.len()));
+            self.text_content = self.clean_page_text(&self.text_content);
 
-        // 3. Robust Decode
-        // We configure a custom engine to be tolerant of corruption (missing padding, trailing bits).
-        let config = base64::engine::GeneralPurposeConfig::new()
-            .with_decode_allow_trailing_bits(true)
-            .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent);
-            
-        let engine = base64::engine::GeneralPurpose::new(&base64::alphabet::STANDARD, config);
+            // History belongs to a page, start over when we move to another one
+            if index != self.current_page_index {
+                self.undo_stack.clear();
+                self.redo_stack.clear();
+            }
+            self.history_snapshot = self.text_content.clone();
+            self.text_dirty = false;
 
-        match engine.decode(&clean_string) {
-            Ok(bytes) => {
-                self.decode_logs.push(format!("Decoded into {} bytes of binary data", bytes.len()));
-                self.recover_jpegs_from_stream(ctx, &bytes);
-            },
-            Err(e) => {
-                self.decode_logs.push(format!("CRITICAL: Base64 decoding failed even with permissive mode: {}", e));
+            if index != self.current_page_index {
+                self.pinned_selections.clear();
+                self.placeholders.clear();
+                self.placeholder_snapshot = self.text_content.clone();
+                self.char_search = None;
+                // Read again from its file, the edits were either saved or discarded
+                self.page_validity.remove(&self.current_page_index);
+                self.validity_requested.remove(&self.current_page_index);
             }
+            self.current_page_index = index;
+            self.synced_top_line = None; // New page, the view has to follow the editor again
+            self.base64_before_page = self.count_base64_before_page(index);
         }
     }
 
-    fn recover_jpegs_from_stream(&mut self, ctx: &egui::Context, bytes: &[u8]) {
-        // let mut decoder = jpeg_decoder::Decoder::new(bytes);
-        // let metadata = decoder.info().map(|e| self.decode_logs.push(format!("-> Got  image info: {}x{}", e.width, e.height)));
-        // let pixels = decoder.decode().map_err(|e| self.decode_logs.push(format!("-> FAILED to decode image: {}", e)));
+    // Page changes asked for by the user go through here, so unsaved edits aren't lost
+    fn navigate_to_page(&mut self, index: u16) {
+        // Already there, loading again would replace unsaved edits with the file
+        if index == self.current_page_index {
+            return;
+        }
+        if self.can_leave_page(index) {
+            self.load_page(index);
+        }
+    }
 
-        // use zenjpeg::decoder::{Decoder, DecodedImage, DecodedImageF32, DecoderConfig};
-        // if let Ok(info) = Decoder::new()
-        //         .fancy_upsampling(true)
-        //         .block_smoothing(false)
-        //         .decode(bytes).map_err(|e| self.decode_logs.push(format!("-> FAILED to decode image: {}", e))) {
-        //     // self.decode_logs.push(format!("Got image {}x{}, {} components", info.dimensions.width, info.dimensions.height, info.num_components));
-        //     self.decode_logs.push(format!("Got image {}x{}", info.width, info.height));
-        // }
+    // False when the edits need a decision first, the "Unsaved Changes" dialog then finishes the move
+    fn can_leave_page(&mut self, index: u16) -> bool {
+        if !self.text_dirty || index == self.current_page_index {
+            return true;
+        }
+        if self.settings.save_on_page_change {
+            self.save_page();
+            return true;
+        }
+        self.pending_page = Some(index);
+        false
+    }
 
+    // Renders the page keeping its aspect ratio, `longest_side` is in pixels
+    fn render_page(page: &PdfPage, longest_side: i32, settings: &Settings) -> Result<egui::ColorImage, PdfiumError> {
+        let aspect = page.width().value / page.height().value;
+        let (width, height) = if aspect >= 1.0 {
+            (longest_side, (longest_side as f32 / aspect) as i32)
+        } else {
+            ((longest_side as f32 * aspect) as i32, longest_side)
+        };
 
-        // let mut decoder = zune_jpeg::JpegDecoder::new(std::io::Cursor::new(bytes));
-        // // decode the file
-        // let pixels = decoder.decode().map_err(|e| self.decode_logs.push(format!("-> FAILED to decode image: {}", e)));
+        // Faint scans read better without smoothing, on a plain background, in grayscale
+        let [r, g, b] = settings.render_background;
+        let config = PdfRenderConfig::new()
+            .set_target_size(width.max(1), height.max(1))
+            .set_clear_color(PdfColor::new(r, g, b, 255))
+            .set_text_smoothing(settings.render_antialiasing)
+            .set_path_smoothing(settings.render_antialiasing)
+            .set_image_smoothing(settings.render_antialiasing)
+            .use_grayscale_rendering(settings.render_grayscale);
+        let bitmap = page.render_with_config(&config)?;
+        let image = bitmap.as_image();
+        let size = [image.width() as usize, image.height() as usize];
+        Ok(egui::ColorImage::from_rgb(size, &image.into_rgb8()))
+    }
 
+    // The render settings changed, everything rendered so far is stale.
+    // Only the texture of the current page is replaced, its text stays as it is.
+    fn rerender_pages(&mut self) {
+        self.page_texture_cache.clear();
+        self.page_texture_lru.clear();
+        self.clear_thumbnails();
 
-        // Attempt to decode
-        match image::load_from_memory_with_format(bytes, image::ImageFormat::Jpeg) {
-            Ok(img) => {
-                let size = [img.width() as usize, img.height() as usize];
-                let color_image = egui::ColorImage::from_rgb(size, &img.to_rgb8());
-                
-                let tex = ctx.load_texture(
-                    "decoded_img",
-                    color_image,
-                    egui::TextureOptions::LINEAR
-                );
-                
-                self.decoded_textures.push(tex);
-                self.decode_logs.push("-> SUCCESS: Recovered image".into());
+        if !self.documents.is_empty() {
+            self.request_render(self.current_page_index);
+        }
+    }
 
-            },
-            Err(e) => {
-                self.decode_logs.push(format!("-> FAILED to decode image: {}", e));
-            }
-        
+    // Background work for the page thread, it does the page of request_render first
+    fn send_page_request(&self, index: u16, generation: u64, job: PageJob) {
+        if let Some(tx) = &self.page_tx {
+            let request = PageRequest {
+                index,
+                generation,
+                job,
+                paths: self.document_paths.clone(),
+                password: self.pdf_password.clone(),
+                render_size: self.render_size,
+                settings: self.settings.clone(),
+            };
+            // The thread only stops when the app does
+            let _ = tx.send(request);
         }
     }
 
+    // Hands the page to the page thread, replacing whatever page it was asked for before
+    fn request_render(&mut self, index: u16) {
+        self.render_generation += 1;
+        self.rendering_page = Some(index);
+        self.page_texture = None;
+        self.send_page_request(index, self.render_generation, PageJob::Render);
+    }
 
-    fn perform_hex_jump(&mut self, ctx: &egui::Context) {
-        // 1. Parse Hex Input
-        let clean_input = self.hex_input.trim().trim_start_matches("0x");
-        let binary_offset = match u64::from_str_radix(clean_input, 16) {
-            Ok(val) => val,
-            Err(_) => {
-                self.jump_status_msg = "Invalid Hexadecimal".to_string();
-                return;
-            }
+    // Uploads what the page thread finished, results for pages or settings since abandoned are dropped
+    fn poll_page_thread(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.page_rx else {
+            return;
         };
 
-        // 2. Calculate Target Base64 Index
-        // Rule: 3 bytes of binary = 4 bytes of Base64.
-        // Formula: (Offset / 3) * 4
-        let target_b64_index = (binary_offset / 3) * 4;
-        
-        self.jump_status_msg = format!("Seeking Hex 0x{:X} -> Base64 Index {}", binary_offset, target_b64_index);
-
-        // 3. Iterate Files
-        let mut current_b64_count: u64 = 0;
-        
-        // Scan directory (reuse sorting logic)
-        let mut files = Vec::new();
-        if let Ok(entries) = fs::read_dir(".") {
-            files = entries.flatten()
-                .filter(|e| {
-                    let name = e.file_name().to_string_lossy().to_string();
-                    name.starts_with("page") && name.ends_with(".txt")
-                })
-                .collect();
-            
-            files.sort_by_key(|e| {
-                let name = e.file_name().to_string_lossy().to_string();
-                let num_str: String = name.chars().filter(|c| c.is_ascii_digit()).collect();
-                num_str.parse::<u32>().unwrap_or(9999)
-            });
-        }
-
-        let mut found_page_index = None;
-        let mut found_cursor_pos = 0;
-
-        'file_loop: for file in files.iter() {
-            if let Ok(content) = fs::read_to_string(file.path()) {
-                // Iterate characters in this file
-                for (char_idx, c) in content.chars().enumerate() {
-                    // Check if it's a valid Base64 char (A-Z, a-z, 0-9, +, /)
-                    // We treat everything else (newlines, spaces) as invisible to the offset count
-                    if c.is_alphanumeric() || c == '+' || c == '/' {
-                        if current_b64_count == target_b64_index {
-                            // FOUND IT!
-                            let name = file.file_name().to_string_lossy().to_string();
-                            let num_str: String = name.chars().filter(|c| c.is_ascii_digit()).collect();
-
-                            if let Ok(page_num) = num_str.parse::<u16>() {
-                                // PDF pages are 0-indexed, File names are usually 1-indexed
-                                found_page_index = Some(if page_num > 0 { page_num - 1 } else { 0 });
-                                found_cursor_pos = char_idx;
-                                break 'file_loop;
-                            }
-                        }
-                        current_b64_count += 1;
+        let results: Vec<PageResult> = rx.try_iter().collect();
+        for result in results {
+            match result {
+                PageResult::Rendered { index, generation, image } => {
+                    if generation != self.render_generation {
+                        continue;
+                    }
+                    let texture =
+                        ctx.load_texture(format!("pdf_page_{}", index), image, egui::TextureOptions::LINEAR);
+                    self.page_texture_cache.insert(index, texture.clone());
+                    Self::touch_page_texture(&mut self.page_texture_lru, &mut self.page_texture_cache, index);
+                    self.page_texture = Some(texture);
+                    self.rendering_page = None;
+                }
+                PageResult::RenderFailed { index, generation, error } => {
+                    if generation != self.render_generation {
+                        continue;
+                    }
+                    self.rendering_page = None;
+                    self.status_msg = format!("Could not render page {}: {}", index + 1, error);
+                    self.decode_logs.push(self.status_msg.clone());
+                }
+                PageResult::Thumbnail { index, generation, image } => {
+                    // Asked for again since, the set already holds the new request
+                    if generation != self.overview_generation {
+                        continue;
                     }
+                    let texture =
+                        ctx.load_texture(format!("pdf_thumbnail_{}", index), image, egui::TextureOptions::LINEAR);
+                    self.thumbnail_cache.insert(index, texture);
+                }
+                PageResult::Validity { index, generation, validity } => {
+                    if generation != self.overview_generation {
+                        continue;
+                    }
+                    self.page_validity.insert(index, validity);
                 }
             }
         }
+    }
 
-        // 4. Act on Result
-        if let Some(idx) = found_page_index {
-            // Load the page
-            self.load_page(ctx, idx as u16);
-            self.jump_status_msg = format!("Found on Page {}, Char {}", idx + 1, found_cursor_pos);
-            self.show_hex_dialog = false; // Close dialog
-
-            // Set Cursor and Focus
-            let text_id = egui::Id::new("shared_pdf_editor_id");
-            if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-                state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
-                    egui::text::CCursor::new(found_cursor_pos)
-                )));
-                state.store(ctx, text_id);
-                ctx.memory_mut(|m| m.request_focus(text_id));
-            }
-        } else {
-            self.jump_status_msg = format!("Offset out of bounds. Max Base64 len: {}", current_b64_count);
+    // Thumbnails are small, so unlike the full pages we keep all of them once rendered.
+    // None until the page thread has rendered it, the first call asks for it.
+    fn thumbnail_texture(&mut self, index: u16) -> Option<egui::TextureHandle> {
+        if let Some(texture) = self.thumbnail_cache.get(&index) {
+            return Some(texture.clone());
         }
+        if self.thumbnails_requested.insert(index) {
+            self.send_page_request(index, self.overview_generation, PageJob::Thumbnail);
+        }
+        None
     }
 
-    fn cycle_common_problem(&mut self, state: egui::text_edit::TextEditState) {
-        if let Some(range) = state.cursor.char_range() {
-            let idx = range.primary.index;
-            let current_char = self.text_content.chars().nth(idx).unwrap_or('*');
-            let next_char = self.text_content.chars().nth(idx + 1).unwrap_or('*');
-            let third_char = self.text_content.chars().nth(idx + 2).unwrap_or('*');
+    fn clear_thumbnails(&mut self) {
+        self.thumbnail_cache.clear();
+        self.thumbnails_requested.clear();
+        self.overview_generation += 1;
+    }
 
-            if let Some(replacement) = match (current_char, next_char, third_char) {
-                (' ', '1', ' ') => Some((3, "1")),
-                (' ', 'I', ' ') => Some((3, "1")),
-                (' ', 'l', ' ') => Some((3, "1")),
-                ('-', 'F', _) => Some((2, "+")),
-                ('r', 'n', _) => Some((2, "m")),
-                ('I', 'c', _) => Some((2, "k")),
-                ('I', 'C', _) => Some((2, "K")),
-                ('3', 'C', _) => Some((2, "X")),
-                ('I', 'J', _) => Some((2, "U")),
-                ('m', _, _) => Some((1, "rn")),
-                ('I', _, _) => Some((1, "l")),
-                ('l', _, _) => Some((1, "1")),
-                ('1', _, _) => Some((1, "I")),
-                ('O', _, _) => Some((1, "0")),
-                ('0', _, _) => Some((1, "O")),
-                ('g', _, _) => Some((1, "q")),
-                ('q', _, _) => Some((1, "g")),
-                ('c', _, _) => Some((1, "e")),
-                ('e', _, _) => Some((1, "c")),
-                ('Y', _, _) => Some((1, "V")),
-                ('V', _, _) => Some((1, "Y")),
-                _ => None
-            } {
-                self.text_content.replace_range(idx..idx+replacement.0, &replacement.1);
-            }
+    // Minimap stats of a page other than the current one, asked of the page thread like thumbnail_texture
+    fn page_validity(&mut self, index: u16) -> Option<PageValidity> {
+        if let Some(validity) = self.page_validity.get(&index) {
+            return Some(*validity);
+        }
+        if self.validity_requested.insert(index) {
+            let job = PageJob::Validity(self.alphabet, self.page_file_path(index));
+            self.send_page_request(index, self.overview_generation, job);
         }
+        None
     }
 
-    fn get_current_line_number(&self, ctx: &egui::Context) -> String {
-        let text_id = egui::Id::new("shared_pdf_editor_id");
-        
-        if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-            if let Some(range) = state.cursor.char_range() {
-                // Get the index of the cursor (primary selection point)
-                let char_idx = range.primary.index;
-
-                // Count newlines up to the cursor position
-                // We add 1 because humans count lines starting from 1, not 0
-                let line_count = self.text_content.chars()
-                    .take(char_idx)
-                    .filter(|&c| c == '\n')
-                    .count();
+    // The pages have to be read again, after edits to their files or a change of what counts as valid
+    fn clear_page_validity(&mut self) {
+        self.page_validity.clear();
+        self.validity_requested.clear();
+        self.overview_generation += 1;
+    }
 
-                return format!("{}", line_count + 1);
-            }
+    // The line indicators of the editor for the whole document: one thin segment per page,
+    // to spot the corrupted page among hundreds. Click a segment to go there.
+    fn show_validity_minimap(&mut self, ctx: &egui::Context) {
+        if self.total_pages == 0 {
+            return;
         }
-        "?".to_string()
-    }
 
-    fn display_script() {
-        // Determine script name based on OS
-        #[cfg(target_os = "windows")]
-        let script = "display_script.bat";
-        #[cfg(not(target_os = "windows"))]
-        let script = "./display_script.sh";
+        egui::SidePanel::right("validity_minimap")
+            .resizable(false)
+            .exact_width(MINIMAP_WIDTH)
+            .show(ctx, |ui| {
+                let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click());
+                let segment_height = rect.height() / self.total_pages as f32;
 
-        println!("Running script: {}", script);
+                for index in 0..self.total_pages {
+                    let validity = if index == self.current_page_index {
+                        Some(PageValidity::of(&self.text_content, self.alphabet, self.settings.line_length))
+                    } else {
+                        self.page_validity(index)
+                    };
 
-        // Execute the script
-        let child = if cfg!(target_os = "windows") {
-            Command::new("cmd").args(["/C", script]).spawn()
-        } else {
-            Command::new("sh").arg(script).spawn()
-        };
-        child.expect("Failed to launch display script");
-    }
+                    let segment = egui::Rect::from_min_size(
+                        rect.min + egui::vec2(0.0, index as f32 * segment_height),
+                        egui::vec2(rect.width(), segment_height.max(1.0)),
+                    );
+                    let color = validity.map_or(ui.visuals().faint_bg_color, PageValidity::color);
+                    ui.painter().rect_filled(segment, 0.0, color);
+                }
 
-    // Warning, this function is LLM-generated. Mostly.
-    // Well, the AI code did not compile and used deprecated APIs. I fixed those. But the logic is from the LLM...
-    fn adjust_line_spaces_to_pdf(&mut self, ctx: &egui::Context) {
-        let text_id: egui::Id = egui::Id::new("shared_pdf_editor_id");
-        
-        if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-            if let Some(range) = state.cursor.char_range() {
-                let cursor_idx = range.primary.index;
-                let chars: Vec<char> = self.text_content.chars().collect();
+                let current_y = rect.top() + (self.current_page_index as f32 + 0.5) * segment_height;
+                ui.painter().hline(
+                    rect.x_range(),
+                    current_y,
+                    egui::Stroke::new(2.0, ui.visuals().strong_text_color()),
+                );
 
-                // 1. Identify Key Indices in the Text Editor
-                // Find start of current line (scan back)
-                let mut line_start_idx = cursor_idx;
-                while line_start_idx > 0 && chars[line_start_idx - 1] != '\n' {
-                    line_start_idx -= 1;
+                if let Some(pos) = response.hover_pos() {
+                    let index = (((pos.y - rect.top()) / segment_height) as u16).min(self.total_pages - 1);
+                    let validity = if index == self.current_page_index {
+                        Some(PageValidity::of(&self.text_content, self.alphabet, self.settings.line_length))
+                    } else {
+                        self.page_validity.get(&index).copied()
+                    };
+                    let hover = match validity {
+                        Some(validity) => format!("Page {}: {}", index + 1, validity.describe()),
+                        None => format!("Page {}", index + 1),
+                    };
+                    if response.on_hover_text(hover).clicked() {
+                        self.navigate_to_page(index);
+                    }
                 }
+            });
+    }
 
-                // Find end of current line (scan forward to \n)
-                let mut newline_idx = None;
-                for i in cursor_idx..chars.len() {
-                    if chars[i] == '\n' {
-                        newline_idx = Some(i);
-                        break;
+    // Replace any 0x0D character with spaces, unless the settings say to keep them
+    // (the decode ignores them anyway)
+    fn clean_page_text(&self, text: &str) -> String {
+        Self::clean_carriage_returns(text, self.settings.keep_carriage_returns)
+    }
+
+    // The CR of a CRLF line break goes with it, or every save with CRLF line endings
+    // would add a trailing space to each line. Only a lone CR becomes a space.
+    fn clean_carriage_returns(text: &str, keep: bool) -> String {
+        if keep {
+            return text.to_string();
+        }
+        text.replace("\r\n", "\n")
+            .chars()
+            .map(|c| if c == '\u{0D}' { ' ' } else { c })
+            .collect()
+    }
+
+    // Text of the page as extracted from the PDF, ignoring any saved file
+    fn extract_page_text(&self, index: u16) -> Option<String> {
+        let page = Self::document_page(&self.documents, index)?;
+        let text = page.text().ok()?;
+        Some(self.clean_page_text(&text.all()))
+    }
+
+    // Pages (other than the current one) whose saved file differs from the PDF text
+    fn pages_with_manual_edits(&self) -> Vec<u16> {
+        (0..self.total_pages)
+            .filter(|&index| index != self.current_page_index)
+            .filter(|&index| {
+                match fs::read_to_string(self.page_file_path(index)) {
+                    // Saved with the line ending setting, which is not an edit
+                    Ok(content) => {
+                        let line_ending = self.settings.line_ending;
+                        Some(line_ending.normalize(&self.clean_page_text(&content)))
+                            != self.extract_page_text(index).map(|text| line_ending.normalize(&text))
                     }
+                    Err(_) => false,
                 }
+            })
+            .collect()
+    }
 
-                if let Some(nl_idx) = newline_idx {
-                    // Find Last Non-Space Char of Current Line (A)
-                    let mut idx_a = nl_idx;
-                    while idx_a > line_start_idx && chars[idx_a - 1].is_whitespace() {
-                        idx_a -= 1;
-                    }
-                    // idx_a points to the first space *after* the text, or the newline if no spaces.
-                    // So idx_a - 1 is the actual character.
-                    if idx_a == line_start_idx { return; } // Empty line, nothing to align against
-                    let last_char_idx = idx_a - 1;
+    fn request_save_all_pages(&mut self) {
+        self.save_all_conflicts = self.pages_with_manual_edits();
+        if self.save_all_conflicts.is_empty() {
+            self.save_all_pages(false);
+        } else {
+            self.show_save_all_dialog = true;
+        }
+    }
 
-                    // Find First Char of Next Line (B)
-                    // It is the character immediately after the newline (nl_idx + 1)
-                    let next_line_start_idx = nl_idx + 1;
-                    if next_line_start_idx >= chars.len() { return; } // EOF
+    fn save_all_pages(&mut self, overwrite_edited: bool) {
+        self.decode_logs.clear();
+        self.decode_logs.push(format!("Saving all {} pages...", self.total_pages));
 
-                    // 2. Consult PDF Logic
-                    if let Some(doc) = &self.document {
-                        if let Ok(page) = doc.pages().get(self.current_page_index) {
-                            if let Ok(text_page) = page.text() {
-                                
-                                // Get the PDF Rect for Item A (Last char of current line)
-                                // We use an iterator to be safe
-                                let chars = text_page.chars();
-                                let obj_a_opt = chars.iter().nth(last_char_idx);
-                                
-                                if let Some(obj_a) = obj_a_opt {
-                                    if let Ok(rect_a) = obj_a.loose_bounds() {
-                                        
-                                        // 3. Scan PDF for the "True" Start of Next Line
-                                        // We look for the first character *after* A that satisfies the visual requirements:
-                                        // - Below A
-                                        // - Leftmost (reset X)
-                                        let mut found_pdf_target_idx = None;
-                                        
-                                        // Scan forward in PDF from A
-                                        for (offset, char_obj) in text_page.chars().iter().skip(last_char_idx + 1).enumerate() {
-                                            if let Ok(rect_curr) = char_obj.loose_bounds() {
-                                                let is_below = rect_curr.top().value < (rect_a.bottom().value + 2.0); // Tolerance
-                                                let is_leftmost = rect_curr.left().value < rect_a.left().value;
-                                                
-                                                if is_below && is_leftmost {
-                                                    // Found it! 
-                                                    // The index in PDF is last_char_idx + 1 + offset
-                                                    found_pdf_target_idx = Some(last_char_idx + 1 + offset);
-                                                    break;
-                                                }
-                                            }
-                                        }
+        let mut saved = 0;
+        let mut skipped = 0;
+        for index in 0..self.total_pages {
+            // The current page may have in-app edits, save those instead of the PDF text
+            if index == self.current_page_index {
+                self.save_page();
+                saved += 1;
+                continue;
+            }
 
-                                        // 4. Calculate Adjustment
-                                        if let Some(target_idx) = found_pdf_target_idx {
-                                            // The text editor currently thinks the next line starts at `next_line_start_idx`.
-                                            // The PDF says the next visual line starts at `target_idx`.
-                                            // The difference must be absorbed by spaces at the end of the current line.
-                                            
-                                            let current_diff = (next_line_start_idx as i32) - (last_char_idx as i32);
-                                            let target_diff = (target_idx as i32) - (last_char_idx as i32);
-                                            
-                                            // How many spaces do we need to add/remove?
-                                            // current_diff includes the spaces + the newline (1)
-                                            // target_diff includes the spaces + the newline (1) (logically)
-                                            let needed_change = target_diff - current_diff;
-
-                                            let mut final_newline_idx = nl_idx;
-
-                                            if needed_change > 0 {
-                                                // We are short on indices. Add spaces.
-                                                let spaces = " ".repeat(needed_change as usize);
-                                                // Insert before newline
-                                                let byte_offset = self.text_content.char_indices().nth(nl_idx).unwrap().0;
-                                                self.text_content.insert_str(byte_offset, &spaces);
-                                                final_newline_idx = nl_idx + needed_change as usize;
-                                                println!("Adjusted: Added {} spaces.", needed_change);
-                                            } else if needed_change < 0 {
-                                                // We have too many indices (too many spaces). Remove them.
-                                                let remove_count = (-needed_change) as usize;
-                                                
-                                                // Ensure we only remove spaces, don't eat text.
-                                                // Available spaces = nl_idx - idx_a
-                                                let available_spaces = nl_idx - idx_a;
-                                                
-                                                let safe_remove = remove_count.min(available_spaces);
-                                                
-                                                if safe_remove > 0 {
-                                                    let start_remove_idx = nl_idx - safe_remove;
-                                                    let byte_start = self.text_content.char_indices().nth(start_remove_idx).unwrap().0;
-                                                    let byte_end = self.text_content.char_indices().nth(nl_idx).unwrap().0;
-                                                    
-                                                    self.text_content.replace_range(byte_start..byte_end, "");
-                                                    final_newline_idx = nl_idx - safe_remove;
-                                                    println!("Adjusted: Removed {} spaces.", safe_remove);
-                                                }
-                                            }
+            if !overwrite_edited && self.save_all_conflicts.contains(&index) {
+                self.decode_logs.push(format!("-> Skipped page {}: file has manual edits", index + 1));
+                skipped += 1;
+                continue;
+            }
 
-                                            let new_cursor_pos = final_newline_idx + 1;
-                                            
-                                            state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
-                                                egui::text::CCursor::new(new_cursor_pos)
-                                            )));
-                                            
-                                            state.store(ctx, text_id);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            let file_name = self.page_file_path(index);
+            match self.extract_page_text(index) {
+                Some(text) => match fs::write(&file_name, self.settings.line_ending.normalize(&text)) {
+                    Ok(_) => saved += 1,
+                    Err(e) => self.decode_logs.push(format!("-> Error saving file {}: {}", file_name.display(), e)),
+                },
+                None => self.decode_logs.push(format!("-> Could not extract text of page {}", index + 1)),
             }
         }
+
+        self.decode_logs.push(format!("Saved {} pages, skipped {}", saved, skipped));
+        self.base64_before_page = self.count_base64_before_page(self.current_page_index);
+        self.clear_page_validity();
+        self.save_all_conflicts.clear();
     }
-}
 
-impl eframe::App for PdfApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let text_id = egui::Id::new("shared_pdf_editor_id");
-            ui.horizontal(|ui| {
-                if ui.button("Prev").clicked() && self.current_page_index > 0 {
-                    self.load_page(ctx, self.current_page_index - 1);
-                }
-                ui.label(format!(
-                    "Page {} / {}",
-                    self.current_page_index + 1,
-                    self.total_pages
-                ));
-                if ui.button("Next").clicked() && self.current_page_index < self.total_pages - 1 {
-                    self.load_page(ctx, self.current_page_index + 1);
-                }
+    // Mark the page as most recently used and evict the oldest ones over the limit
+    // Takes the fields separately so it can be called while the document is borrowed
+    fn touch_page_texture(
+        lru: &mut VecDeque<u16>,
+        cache: &mut HashMap<u16, egui::TextureHandle>,
+        index: u16,
+    ) {
+        lru.retain(|&i| i != index);
+        lru.push_back(index);
 
-                ui.separator();
+        while lru.len() > PAGE_TEXTURE_CACHE_SIZE {
+            if let Some(oldest) = lru.pop_front() {
+                cache.remove(&oldest);
+            }
+        }
+    }
 
-                if ui.button("Jump to I/l/1").clicked() {
-                    self.jump_to_ilone(ctx);
-                }
+    fn get_highlights(&self, selection: egui::text::CCursorRange) -> Vec<egui::Rect> {
+        let mut rects = Vec::new();
 
-                if ui.button("Hex Jump").clicked() {
-                    self.show_hex_dialog = true;
-                    self.hex_input.clear();
-                    self.jump_status_msg.clear();
-                }
+        if let Some(page) = Self::document_page(&self.documents, self.current_page_index) {
+            let boundaries = page.boundaries();
+            let crop = boundaries
+                .crop()
+                .unwrap_or(boundaries.media().expect("Neither crop no media present"));
 
-                ui.separator();
+            let p_width = crop.bounds.width().value;
+            let p_height = crop.bounds.height().value;
+            let p_left_offset = crop.bounds.left().value;
+            let _p_bottom_offset = crop.bounds.bottom().value;
+            // In PDF, 'top' is the highest Y value.
+            // We use this to flip the Y-axis.
+            let p_top_value = crop.bounds.top().value;
 
-                if ui.button("Save").clicked() {
-                    self.save_page();
-                    self.run_stream_decoding(ctx);
-                }
+            if let Ok(text_page) = page.text() {
+                // Egui gives us char indices (CCursor), not byte indices
+                let start_char = selection.primary.index.min(selection.secondary.index);
+                let end_char = selection.primary.index.max(selection.secondary.index);
 
-                // Keyboard shortcuts
-                if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.ctrl) {
-                    self.save_page();
-                    self.run_stream_decoding(ctx);
-                }
-                if ctx.input(|i| i.key_pressed(egui::Key::J) && i.modifiers.ctrl) {
-                    self.jump_to_ilone(ctx);
-                }
-                if ctx.input(|i| i.key_pressed(egui::Key::G) && i.modifiers.ctrl) {
-                    self.show_hex_dialog = true;
-                    self.hex_input.clear();
-                    self.jump_status_msg.clear();
-                }
-                // Keyboard shortcuts
-                if ctx.input(|i| i.key_pressed(egui::Key::D) && i.modifiers.ctrl) {
-                    Self::display_script();
-                }
+                // Each range is (start char index, char count)
+                let mut ranges = Vec::new();
 
-                if ui.button("Display").clicked() {
-                    Self::display_script();
+                if self.highlight_all_matches && start_char != end_char {
+                    // Every occurrence of the selected text on the page
+                    let needle: String = self
+                        .text_content
+                        .chars()
+                        .skip(start_char)
+                        .take(end_char - start_char)
+                        .collect();
+                    let needle_chars = needle.chars().count();
+                    let matches = if needle.is_empty() {
+                        Vec::new()
+                    } else {
+                        self.text_content.match_indices(needle.as_str()).collect()
+                    };
+                    for (byte_idx, _) in matches {
+                        // match_indices gives bytes, the PDF chars are counted in chars
+                        let char_idx = self.text_content[..byte_idx].chars().count();
+                        ranges.push((char_idx, needle_chars));
+                    }
+                } else if start_char <= self.text_content.chars().count() {
+                    // The selection, or the char after the cursor when nothing is selected
+                    let char_count = if start_char == end_char { 1 } else { end_char - start_char };
+                    ranges.push((start_char, char_count));
                 }
-            });
-
-            // --- TOP SECTION: PDF VIEW ---
-            egui::ScrollArea::vertical()
-                .max_height(80.0)// .max_height(available_height * 0.1)
-                .id_salt("pdf_scroll")
-                .show(ui, |ui| {
-                    if let Some(texture) = &self.page_texture {
-                        let size = texture.size_vec2();
-                        let scale = ui.available_width() / size.x;
-                        let display_size = size * scale;
-
-                        let (rect, _response) =
-                            ui.allocate_exact_size(display_size, egui::Sense::click());
-                        let painter = ui.painter_at(rect);
-                        painter.image(
-                            texture.id(),
-                            rect,
-                            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                            egui::Color32::WHITE,
-                        );
-
-                        if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-                            if let Some(range) = state.cursor.char_range() {
-                                let highlights = self.get_highlights(range);
-
-                                if let Some(first_rect) = highlights.first() {
-                                    let screen_min = rect.min
-                                        + egui::vec2(
-                                            first_rect.min.x * display_size.x,
-                                            first_rect.min.y * display_size.y,
-                                        );
-                                    let screen_max = rect.min
-                                        + egui::vec2(
-                                            first_rect.max.x * display_size.x,
-                                            first_rect.max.y * display_size.y,
-                                        );
-                                    let cursor_screen_rect =
-                                        egui::Rect::from_min_max(screen_min, screen_max);
-
-                                    // Tell Egui to scroll here if it's off-screen
-                                    // None = Minimal scroll (just bring it into view)
-                                    // Some(Align::Center) = Always center it
-                                    ui.scroll_to_rect(cursor_screen_rect, None);
-                                }
 
-                                for h_rect_norm in highlights {
-                                    // Convert normalized coordinates (0..1) back to Screen Pixels
-                                    let screen_min = rect.min
-                                        + egui::vec2(
-                                            h_rect_norm.min.x * display_size.x,
-                                            h_rect_norm.min.y * display_size.y,
-                                        );
-                                    let screen_max = rect.min
-                                        + egui::vec2(
-                                            h_rect_norm.max.x * display_size.x,
-                                            h_rect_norm.max.y * display_size.y,
-                                        );
-
-                                    // Rectangle mode
-                                    // let screen_rect =
-                                    //     egui::Rect::from_min_max(screen_min, screen_max);
-
-                                    // painter.rect_stroke(
-                                    //     screen_rect,
-                                    //     0.0,
-                                    //     egui::Stroke::new(2.0, egui::Color32::GREEN),
-                                    //     egui::StrokeKind::Outside,
-                                    // );
-
-                                    // Underline mode
-                                    let line_y = screen_max.y; // Bottom of the rectangle
-                                    let line_start = egui::pos2(screen_min.x - 2.0, line_y); // Extend slightly to the left
-                                    let line_end = egui::pos2(screen_max.x + 2.0, line_y);   // Extend slightly to the right
-                                
-                                    // Draw a bold green line under the letter
-                                    painter.line_segment(
-                                        [line_start, line_end],
-                                        egui::Stroke::new(4.0, egui::Color32::GREEN), // Bold line
-                                    );
+                let chars = text_page.chars();
+                for (start_char_idx, char_count) in ranges {
+                    for char_obj in chars.iter().skip(start_char_idx).take(char_count) {
+                        if let Ok(rect) = char_obj.loose_bounds() {
+                            // We calculate coordinates RELATIVE to the page dimensions (0.0 to 1.0)
+                            // This helps if the rendered image has been cropped or scaled differently.
+                            let left_pct = (rect.left().value - p_left_offset) / p_width;
+                            let top_pct = (p_top_value - rect.top().value) / p_height;
+                            let width_pct = (rect.right().value - rect.left().value) / p_width;
+                            let height_pct =
+                                (rect.top().value - rect.bottom().value) / p_height;
 
-                                }
-                            }
+                            rects.push(egui::Rect::from_min_size(
+                                egui::pos2(left_pct, top_pct),
+                                egui::vec2(width_pct, height_pct),
+                            ));
                         }
                     }
-                });
+                }
+            }
+        }
+        rects
+    }
 
-            ui.separator();
+    fn page_file_path(&self, index: u16) -> PathBuf {
+        self.workdir.join(PageFilePattern::of(&self.settings).file_name(index + 1))
+    }
 
-            // --- BOTTOM SECTION: SPLIT EDITOR ---
-            egui::ScrollArea::vertical()
-                .id_salt("text_scroll")
-                .max_height(80.0)
-                .show(ui, |ui| {
-                    ui.horizontal(|ui| {
-                        // SETUP FONTS
-                        let font_size = 24.0;
-                        // We define the font here so we can use metrics for both the indicator and the editor
-                        let font_id = egui::FontId::new(font_size, egui::FontFamily::Monospace);
-                        let row_height = ui.fonts_mut(|f| f.row_height(&font_id)) * 1.015;
-
-                        // 1. LEFT PANEL: STATUS INDICATORS
-                        // We allocate a vertical strip. Width = 15px.
-                        // Height = total lines * row height.
-                        let total_lines = self.text_content.lines().count().max(1);
-                        let desired_height = total_lines as f32 * row_height;
-
-                        // Allocate space for the indicators
-                        let (rect, _response) = ui.allocate_exact_size(
-                            egui::vec2(15.0, desired_height),
-                            egui::Sense::hover(),
-                        );
+    // False when the file could not be written
+    fn save_page(&mut self) -> bool {
+        let filename = self.page_file_path(self.current_page_index);
 
-                        // Draw the indicators
-                        let painter = ui.painter_at(rect);
-                        for (i, line) in self.text_content.lines().enumerate() {
-                            let char_count = line.trim().chars().count();
+        self.last_save = Instant::now();
+        if let Err(e) = fs::write(&filename, self.settings.line_ending.normalize(&self.text_content)) {
+            eprintln!("Error saving file {}: {}", filename.display(), e);
+            false
+        } else {
+            println!("Saved text to {}", filename.display());
+            self.text_dirty = false;
+            self.text_from_file = true;
+            true
+        }
+    }
 
-                            let invalid_count = line.trim().chars().filter(|&c| !BASE64_ALPHABET.contains(c)).count();
-                        
-                            // Check rule: Exactly 76 characters
-                            let color = if invalid_count > 0 {
-                                egui::Color32::ORANGE
-                            } else if char_count == 76 {
-                                egui::Color32::GREEN
-                            } else {
-                                egui::Color32::from_gray(50) // Dim gray for other lines
-                            };
+    // Save (Ctrl+S): the page is written and everything decoded again, a toast says when it's done
+    fn save_and_decode(&mut self, ctx: &egui::Context) {
+        let page = self.current_page_index + 1;
+        if self.save_page() {
+            self.run_stream_decoding(ctx);
+            self.toast_after_decode = Some(format!("Saved page {}", page));
+        } else {
+            self.toast = Some((format!("Could not save page {}", page), Instant::now()));
+        }
+    }
 
-                            // Calculate position
-                            // Note: TextEdit usually adds a small margin (approx 4.0-8.0px).
-                            // We offset Y slightly to align with the text baseline.
-                            let y_offset = rect.top() + (i as f32 * row_height) + 4.0;
-
-                            painter.rect_filled(
-                                egui::Rect::from_min_size(
-                                    egui::pos2(rect.left(), y_offset),
-                                    egui::vec2(8.0, row_height - 2.0),
-                                ),
-                                2.0, // rounding
-                                color,
-                            );
-                        }
+    // Bottom right, over everything, fading out at the end of TOAST_DURATION
+    fn show_toast(&mut self, ctx: &egui::Context) {
+        let Some((msg, shown)) = &self.toast else {
+            return;
+        };
+        let elapsed = shown.elapsed();
+        if elapsed >= TOAST_DURATION {
+            self.toast = None;
+            return;
+        }
+        let fading = elapsed.saturating_sub(TOAST_DURATION - TOAST_FADE);
+        let opacity = 1.0 - fading.as_secs_f32() / TOAST_FADE.as_secs_f32();
 
-                        if ctx.input(|i| i.key_pressed(egui::Key::Space) && i.modifiers.ctrl) {
-                            if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
-                                self.cycle_common_problem(state);
+        egui::Area::new(egui::Id::new("toast"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.set_opacity(opacity);
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(msg);
+                });
+            });
+        ctx.request_repaint();
+    }
+
+    // Joins the page into one run of Base64 and wraps it again at the configured line length.
+    // The decoded bytes don't change, picked up by track_history so it can be undone.
+    fn rewrap_page(&mut self) {
+        let line_length = self.settings.line_length.max(1);
+        let chars: Vec<char> = self.text_content.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut rewrapped: Vec<String> = chars.chunks(line_length).map(|line| line.iter().collect()).collect();
+        if self.text_content.ends_with('\n') {
+            rewrapped.push(String::new());
+        }
+        self.text_content = rewrapped.join("\n");
+        self.status_msg = format!("Rewrapped the page to {} characters per line", line_length);
+    }
+
+    fn request_reload_page_file(&mut self) {
+        if self.text_dirty {
+            self.show_reload_dialog = true;
+        } else {
+            self.reload_page_file();
+        }
+    }
+
+    // Pulls in changes made to the page file outside of the app, can be undone like any edit
+    fn reload_page_file(&mut self) {
+        let path = self.page_file_path(self.current_page_index);
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                self.text_content = self.clean_page_text(&content);
+                self.track_history();
+                self.text_dirty = false; // Same as the file now
+                self.text_from_file = true;
+                self.status_msg = format!("Reloaded {}", path.display());
+            }
+            Err(e) => self.status_msg = format!("Could not reload {}: {}", path.display(), e),
+        }
+    }
+
+    fn autosave_if_needed(&mut self, ctx: &egui::Context) {
+        if let Some(interval) = self.autosave_interval {
+            if self.text_dirty && self.last_save.elapsed() >= interval {
+                self.save_page();
+                self.last_autosave = Some(Instant::now());
+            }
+            // Wake up even if the user is idle so the timer can fire
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+
+    // Jumps to the next of settings.ambiguous_chars (I/l/1 by default)
+    fn jump_to_ilone(&self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+                    
+        // 1. Get current cursor position (default to 0 if not set)
+        let current_idx = if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+             state.cursor.char_range().map(|r| r.primary.index).unwrap_or(0)
+        } else {
+            0
+        };
+
+        // 2. Search for next char starting AFTER current cursor
+        if let Some(new_index) = Self::next_ambiguous_char(&self.text_content, current_idx, &self.settings.ambiguous_chars) {
+            // 3. Mutate the TextEdit State
+            if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+                // Set cursor to the new index
+                state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
+                    egui::text::CCursor::new(new_index)
+                )));
+                
+                // Save the state back
+                state.store(ctx, text_id);
+                
+                // 4. Focus the editor so user can type immediately
+                ctx.memory_mut(|m| m.request_focus(text_id));
+            }
+        }
+    }
+
+    // Char index of the first of `targets` after the char at `cursor`
+    fn next_ambiguous_char(text: &str, cursor: usize, targets: &[char]) -> Option<usize> {
+        // The cursor is a char index, convert it to a byte index before slicing
+        // (non-ASCII chars take more than one byte)
+        let search_from = cursor + 1;
+        let (byte_start, _) = text.char_indices().nth(search_from)?;
+        let slice = &text[byte_start..];
+
+        // Find the offset within the slice, and convert it back to chars
+        let byte_offset = slice.find(targets)?;
+        Some(search_from + slice[..byte_offset].chars().count())
+    }
+
+    // Jumps to the start of the next line whose length isn't settings.line_length,
+    // wrapping around at the end of the page. Blank lines are skipped.
+    fn jump_to_next_wrong_length(&mut self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        let current_idx = egui::text_edit::TextEditState::load(ctx, text_id)
+            .and_then(|state| state.cursor.char_range())
+            .map(|r| r.primary.index)
+            .unwrap_or(0);
+        let current_line = self.text_content.chars().take(current_idx).filter(|&c| c == '\n').count();
+
+        let lines: Vec<&str> = self.text_content.lines().collect();
+        let is_wrong_length = |line: &str| {
+            !line.trim().is_empty()
+                && matches!(
+                    LineStatus::of(line, self.alphabet, self.settings.line_length),
+                    LineStatus::WrongLength(..)
+                )
+        };
+        // The lines after the cursor line, then from the top
+        let found = (current_line + 1..lines.len())
+            .chain(0..=current_line)
+            .find(|&line_idx| lines.get(line_idx).is_some_and(|line| is_wrong_length(line)));
+
+        match found {
+            Some(line_idx) => {
+                self.status_msg = if line_idx <= current_line {
+                    format!("Line {}, wrapped around to the top", line_idx + 1)
+                } else {
+                    String::new()
+                };
+                Self::move_cursor_to(ctx, Self::line_start_char_index(&self.text_content, line_idx));
+                self.scroll_to_cursor = true;
+            }
+            None => {
+                self.status_msg = format!("All lines have {} characters", self.settings.line_length);
+            }
+        }
+    }
+
+    // Selects the cursor line without its line break, so the PDF view highlights where
+    // the whole line is on the page
+    fn select_current_line(&self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) else {
+            return;
+        };
+        let current_idx = state.cursor.char_range().map(|r| r.primary.index).unwrap_or(0);
+        let current_line = self.text_content.chars().take(current_idx).filter(|&c| c == '\n').count();
+
+        let start = Self::line_start_char_index(&self.text_content, current_line);
+        let end = start + self.text_content.chars().skip(start).take_while(|&c| c != '\n').count();
+        state.cursor.set_char_range(Some(egui::text::CCursorRange::two(
+            egui::text::CCursor::new(start),
+            egui::text::CCursor::new(end),
+        )));
+        state.store(ctx, text_id);
+        ctx.memory_mut(|m| m.request_focus(text_id));
+    }
+
+    // Puts the editor cursor on a char index and focuses the editor so the user can type
+    fn move_cursor_to(ctx: &egui::Context, char_idx: usize) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+            state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
+                egui::text::CCursor::new(char_idx),
+            )));
+            state.store(ctx, text_id);
+            ctx.memory_mut(|m| m.request_focus(text_id));
+        }
+    }
+
+    // The saved file of the page to compare with, or its PDF text when it has none (like load_page)
+    fn load_compare_page(&mut self) {
+        let index = self.compare_page_index;
+        self.compare_page_text = fs::read_to_string(self.page_file_path(index))
+            .map(|content| self.clean_page_text(&content))
+            .ok()
+            .or_else(|| self.extract_page_text(index))
+            .unwrap_or_default();
+    }
+
+    // Char frequencies over the saved page files, cleaned like the stream decode sees them
+    fn count_document_histogram(&mut self) {
+        let clean: String = list_page_files(&self.workdir, self.recursive_scan, &PageFilePattern::of(&self.settings))
+            .iter()
+            .filter_map(|file| fs::read_to_string(file).ok())
+            .map(|content| decode::clean_page(&content, self.alphabet, &self.cleaning_filter))
+            .collect();
+        self.document_histogram = decode::char_histogram(&clean, self.alphabet);
+    }
+
+    // One bar per char with the uniform share as a line across, the ambiguous chars in the
+    // highlight color. Hover a bar for its count.
+    fn show_histogram_chart(ui: &mut egui::Ui, counts: &[(char, usize)], ambiguous: &[char], highlight: egui::Color32) {
+        let total: usize = counts.iter().map(|&(_, count)| count).sum();
+        if total == 0 {
+            ui.label("No Base64 characters to count.");
+            return;
+        }
+        let expected = total as f32 / counts.len() as f32;
+        ui.label(format!(
+            "{} characters, {:.1} expected per character if the bytes were random",
+            total, expected
+        ));
+
+        let max = counts.iter().map(|&(_, count)| count).max().unwrap_or(0).max(expected.ceil() as usize);
+        let bar_width = 10.0;
+        let chart_height = 160.0;
+        let label_height = 14.0;
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(bar_width * counts.len() as f32, chart_height + label_height),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        let bar_color = ui.visuals().widgets.inactive.fg_stroke.color;
+        let text_color = ui.visuals().text_color();
+
+        for (i, &(c, count)) in counts.iter().enumerate() {
+            let left = rect.left() + i as f32 * bar_width;
+            let height = chart_height * count as f32 / max as f32;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(left + 1.0, rect.top() + chart_height - height),
+                egui::pos2(left + bar_width - 1.0, rect.top() + chart_height),
+            );
+            let color = if ambiguous.contains(&c) { highlight } else { bar_color };
+            painter.rect_filled(bar, 0.0, color);
+            painter.text(
+                egui::pos2(left + bar_width / 2.0, rect.top() + chart_height),
+                egui::Align2::CENTER_TOP,
+                c,
+                egui::FontId::monospace(9.0),
+                if ambiguous.contains(&c) { highlight } else { text_color },
+            );
+        }
+
+        let expected_y = rect.top() + chart_height - chart_height * expected / max as f32;
+        painter.hline(rect.x_range(), expected_y, egui::Stroke::new(1.0, ui.visuals().warn_fg_color));
+
+        if let Some(pos) = response.hover_pos() {
+            let i = (((pos.x - rect.left()) / bar_width) as usize).min(counts.len() - 1);
+            let (c, count) = counts[i];
+            response.on_hover_text(format!(
+                "'{}': {} ({:.2}%, {:+.0}% vs uniform)",
+                c,
+                count,
+                100.0 * count as f32 / total as f32,
+                100.0 * (count as f32 / expected - 1.0)
+            ));
+        }
+
+        // The furthest from uniform first, those are the ones to look at
+        let mut deviations: Vec<(char, usize)> = counts.to_vec();
+        deviations.sort_by(|a, b| {
+            let deviation = |count: usize| (count as f32 - expected).abs();
+            deviation(b.1).total_cmp(&deviation(a.1))
+        });
+        let furthest: Vec<String> = deviations
+            .iter()
+            .take(5)
+            .map(|&(c, count)| format!("'{}' {:+.0}%", c, 100.0 * (count as f32 / expected - 1.0)))
+            .collect();
+        ui.label(format!("Furthest from uniform: {}", furthest.join(", ")));
+    }
+
+    // A line diff against the editor text: "-" lines are only in the other text (`other_name`),
+    // "+" lines only in the editor. Go puts the cursor on the line, or where a "-" line would be.
+    fn show_diff(
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        lines: &[diff::DiffLine],
+        editor_text: &str,
+        other_name: &str,
+    ) {
+        let changed = lines.iter().filter(|line| line.op != diff::DiffOp::Same).count();
+        if changed == 0 {
+            ui.label(format!("The editor and the {} text are the same.", other_name));
+            return;
+        }
+        ui.label(format!("{} line(s) differ, - {} / + editor", changed, other_name));
+
+        let removed_color = ui.visuals().error_fg_color;
+        let added_color = if ui.visuals().dark_mode { egui::Color32::GREEN } else { egui::Color32::DARK_GREEN };
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("diff_grid").striped(true).show(ui, |ui| {
+                let mut editor_line = 0; // Where the next line would be in the editor
+                for line in lines {
+                    if let Some(new_line) = line.new_line {
+                        editor_line = new_line;
+                    }
+                    let (sign, color) = match line.op {
+                        diff::DiffOp::Same => (' ', ui.visuals().weak_text_color()),
+                        diff::DiffOp::Removed => ('-', removed_color),
+                        diff::DiffOp::Added => ('+', added_color),
+                    };
+                    ui.monospace(line.old_line.map_or(String::new(), |i| (i + 1).to_string()));
+                    ui.monospace(line.new_line.map_or(String::new(), |i| (i + 1).to_string()));
+                    ui.label(egui::RichText::new(format!("{} {}", sign, line.text)).monospace().color(color));
+                    if line.op != diff::DiffOp::Same && ui.small_button("Go").clicked() {
+                        Self::move_cursor_to(ctx, Self::line_start_char_index(editor_text, editor_line));
+                    }
+                    ui.end_row();
+                    if line.new_line.is_some() {
+                        editor_line += 1;
+                    }
+                }
+            });
+        });
+    }
+
+    // Char index of the first char of a 0-based line
+    fn line_start_char_index(text: &str, line_idx: usize) -> usize {
+        if line_idx == 0 {
+            return 0;
+        }
+        text.chars()
+            .enumerate()
+            .filter(|&(_, c)| c == '\n')
+            .nth(line_idx - 1)
+            .map(|(i, _)| i + 1)
+            .unwrap_or_else(|| text.chars().count())
+    }
+
+    // Same as jump_to_ilone, but searching backward from the cursor
+    fn jump_to_prev_ambiguous(&mut self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+
+        let current_idx = egui::text_edit::TextEditState::load(ctx, text_id)
+            .and_then(|state| state.cursor.char_range())
+            .map(|r| r.primary.index)
+            .unwrap_or(0);
+
+        if current_idx == 0 {
+            self.status_msg = "Already at the start of the page".to_string();
+            return;
+        }
+
+        // Char indices of everything before the cursor, nearest first
+        let targets = &self.settings.ambiguous_chars;
+        let found = self
+            .text_content
+            .chars()
+            .take(current_idx)
+            .enumerate()
+            .filter(|(_, c)| targets.contains(c))
+            .map(|(i, _)| i)
+            .last();
+
+        match found {
+            Some(new_index) => {
+                self.status_msg.clear();
+                Self::move_cursor_to(ctx, new_index);
+            }
+            None => {
+                self.status_msg = "No ambiguous character before the cursor".to_string();
+            }
+        }
+    }
+
+    // CORE LOGIC: Load files -> Clean -> Base64 -> Scan for JPEGs
+    // Runs on a worker thread, results come back through decode_rx (see poll_stream_decoding)
+    fn run_stream_decoding(&mut self, ctx: &egui::Context) {
+        let workdir = self.workdir.clone();
+        self.start_decoding(ctx, move |decoder| decoder.run(&workdir));
+    }
+
+    // Decode -> re-encode -> compare, the first difference is where the edits stopped being valid Base64.
+    // Works on the page files like the stream decode, so the current page is saved first.
+    fn run_round_trip_check(&mut self, ctx: &egui::Context) {
+        if self.text_dirty {
+            self.save_page();
+        }
+        let workdir = self.workdir.clone();
+        self.start_decoding(ctx, move |decoder| decoder.run_round_trip(&workdir));
+    }
+
+    // Same pipeline, but for Base64 pasted into the Scratch tab instead of the page files
+    fn run_scratch_decoding(&mut self, ctx: &egui::Context) {
+        let text = self.scratch_text.clone();
+        self.start_decoding(ctx, move |decoder| decoder.run_text("scratch buffer", &text));
+    }
+
+    // Only the text selected in the editor, to try a suspicious block on its own
+    fn run_selection_decoding(&mut self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        let Some(range) = egui::text_edit::TextEditState::load(ctx, text_id).and_then(|state| state.cursor.char_range())
+        else {
+            return;
+        };
+        let start = range.primary.index.min(range.secondary.index);
+        let end = range.primary.index.max(range.secondary.index);
+        if start == end {
+            self.status_msg = "Select some Base64 in the editor first".to_string();
+            return;
+        }
+
+        let text: String = self.text_content.chars().skip(start).take(end - start).collect();
+        let name = format!("selection (page {}, chars {}-{})", self.current_page_index + 1, start, end);
+        self.start_decoding(ctx, move |decoder| decoder.run_text(&name, &text));
+    }
+
+    // Decodes the saved pages before this one plus the editor text up to the end of the
+    // cursor line, to see whether an image is already complete at that point
+    fn run_decode_to_cursor(&mut self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        let char_idx = egui::text_edit::TextEditState::load(ctx, text_id)
+            .and_then(|state| state.cursor.char_range())
+            .map(|range| range.primary.index)
+            .unwrap_or(0);
+
+        // Byte offset of the end of the cursor line, including its newline
+        let start = self
+            .text_content
+            .char_indices()
+            .nth(char_idx)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text_content.len());
+        let line_end = self.text_content[start..]
+            .find('\n')
+            .map(|i| start + i + 1)
+            .unwrap_or(self.text_content.len());
+        let line_number = self.text_content[..line_end].lines().count();
+
+        // The saved pages before this one, the same files as count_base64_before_page
+        let pattern = PageFilePattern::of(&self.settings);
+        let mut file_names = Vec::new();
+        let mut file_contents = Vec::new();
+        let files = list_page_files(&self.workdir, self.recursive_scan, &pattern);
+        for file in files.iter().take_while(|file| {
+            file.parent() == Some(self.workdir.as_path())
+                && pattern.number_of(file).is_some_and(|number| number <= self.current_page_index)
+        }) {
+            if let Ok(content) = fs::read_to_string(file) {
+                file_names.push(file.file_name().unwrap_or_default().to_string_lossy().to_string());
+                file_contents.push(content);
+            }
+        }
+        file_names.push(format!("page {} up to line {}", self.current_page_index + 1, line_number));
+        file_contents.push(self.text_content[..line_end].to_string());
+
+        self.start_decoding(ctx, move |decoder| decoder.run_prefix(&file_names, &file_contents));
+    }
+
+    // Tries every value for the char at `position` of the page: the saved pages, with the
+    // editor text in place of this one, are decoded once per value on the decode thread
+    fn run_char_search(&mut self, ctx: &egui::Context, position: usize) {
+        let alphabet = self.alphabet;
+        if !self.text_content.chars().nth(position).is_some_and(|c| alphabet.is_data_char(c)) {
+            self.status_msg = format!("No {} character to try there", alphabet.name());
+            return;
+        }
+
+        // The files of the decode, in its order, so the values rank by what Decode would recover
+        let pattern = PageFilePattern::of(&self.settings);
+        let mut files = list_page_files(&self.workdir, self.recursive_scan, &pattern);
+        let current = self.page_file_path(self.current_page_index);
+        // Where the page goes in the stream when it hasn't been saved yet: after the pages before it
+        let file = files
+            .iter()
+            .position(|path| *path == current)
+            .unwrap_or_else(|| {
+                let before = files
+                    .iter()
+                    .take_while(|path| {
+                        path.parent() == Some(self.workdir.as_path())
+                            && pattern.number_of(path).is_some_and(|number| number <= self.current_page_index)
+                    })
+                    .count();
+                files.insert(before, current.clone());
+                before
+            });
+
+        let mut file_names = Vec::new();
+        let mut file_contents = Vec::new();
+        let mut editor = 0;
+        for (i, path) in files.iter().enumerate() {
+            // Relative to the workdir like the decode names them
+            let name = path.strip_prefix(&self.workdir).unwrap_or(path).to_string_lossy().to_string();
+            if i == file {
+                editor = file_contents.len();
+                file_names.push(format!("{} (editor)", name));
+                file_contents.push(self.text_content.clone());
+            } else if let Ok(content) = fs::read_to_string(path) {
+                file_names.push(name);
+                file_contents.push(content);
+            }
+        }
+
+        let page = self.current_page_index;
+        self.start_decoding(ctx, move |decoder| {
+            decoder.run_char_search(&file_names, file_contents, editor, page, position)
+        });
+    }
+
+    // The char right after the cursor, same as Ctrl+Space
+    fn run_char_search_at_cursor(&mut self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        match egui::text_edit::TextEditState::load(ctx, text_id)
+            .and_then(|state| state.cursor.char_range())
+            .map(|r| r.primary.index)
+        {
+            Some(idx) => self.run_char_search(ctx, idx),
+            None => self.status_msg = "Put the cursor before the unknown character first".to_string(),
+        }
+    }
+
+    // Puts a value found by Try All in the page, typing over a placeholder drops it from the list
+    fn use_char_candidate(&mut self, position: usize, value: char) {
+        let Some((byte_idx, current)) = self.text_content.char_indices().nth(position) else {
+            return;
+        };
+        self.text_content.replace_range(byte_idx..byte_idx + current.len_utf8(), value.encode_utf8(&mut [0; 4]));
+        // Same char as the placeholder, the diff can't tell it was typed over
+        self.placeholders.retain(|&p| p != position);
+        let (line, column) = Self::line_and_column(&self.text_content, position);
+        self.status_msg = format!("Line {}, column {}: '{}' -> '{}'", line + 1, column + 1, current, value);
+    }
+
+    fn start_decoding(
+        &mut self,
+        ctx: &egui::Context,
+        job: impl FnOnce(&StreamDecoder) + Send + 'static,
+    ) {
+        // Not the decode a pending toast was waiting for, save_and_decode sets it again
+        self.toast_after_decode = None;
+        self.recovered_images.clear();
+        self.decode_logs.clear();
+        self.decode_progress = 0.0;
+        self.decoded_bytes.clear();
+        self.decoded_magic.clear();
+        self.truncated_segment_count = 0;
+        self.decode_source = None;
+        self.char_search = None;
+
+        let (tx, rx) = mpsc::channel();
+        self.decode_rx = Some(rx);
+
+        let decoder = StreamDecoder {
+            tx,
+            ctx: Some(ctx.clone()),
+            alphabet: self.alphabet,
+            filter: self.cleaning_filter.clone(),
+            per_page: self.per_page_decode,
+            recursive: self.recursive_scan,
+            decompress: self.decompress_stream,
+            try_alignments: self.try_alignments,
+            partial_jpeg: self.settings.partial_jpeg_recovery,
+            page_files: PageFilePattern::of(&self.settings),
+        };
+        std::thread::spawn(move || job(&decoder));
+    }
+
+    // Takes whatever the decoding thread sent since the last frame
+    fn poll_stream_decoding(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.decode_rx else {
+            return;
+        };
+
+        loop {
+            match rx.try_recv() {
+                Ok(DecodeMessage::Log(msg)) => self.decode_logs.push(msg),
+                Ok(DecodeMessage::Progress(fraction)) => self.decode_progress = fraction,
+                Ok(DecodeMessage::DecodedBytes(bytes)) => {
+                    // Per page the streams are shown one after another
+                    let offset = self.decoded_bytes.len();
+                    self.decoded_magic.extend(
+                        decode::find_magic_bytes(&bytes)
+                            .into_iter()
+                            .map(|(start, length)| (offset + start, length)),
+                    );
+                    self.decoded_bytes.extend_from_slice(&bytes);
+                }
+                Ok(DecodeMessage::Source(name)) => self.decode_source = Some(name),
+                Ok(DecodeMessage::Truncated) => self.truncated_segment_count += 1,
+                Ok(DecodeMessage::CharSearch(search)) => self.char_search = Some(search),
+                Ok(DecodeMessage::Image {
+                    color_image,
+                    bytes,
+                    format,
+                    offset,
+                    partial,
+                }) => {
+                    let size = color_image.size;
+                    let texture = ctx.load_texture(
+                        format!("decoded_img_{}", self.recovered_images.len()),
+                        color_image,
+                        egui::TextureOptions::LINEAR,
+                    );
+                    let metadata = decode::read_exif(&bytes);
+                    self.recovered_images.push(RecoveredImage {
+                        texture: Some(texture),
+                        size,
+                        offset,
+                        bytes,
+                        format,
+                        source: self.decode_source.clone(),
+                        metadata,
+                        partial,
+                    });
+                    self.enforce_texture_limit();
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    // The thread is done
+                    self.decode_rx = None;
+                    if let Some(saved) = self.toast_after_decode.take() {
+                        let msg = format!("{} · decoded {} image(s)", saved, self.recovered_images.len());
+                        self.toast = Some((msg, Instant::now()));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    // A stream with hundreds of images would fill up the GPU. The oldest images lose their
+    // texture, their entry (size, offset, bytes for Save Images) stays.
+    fn enforce_texture_limit(&mut self) {
+        let limit = self.settings.max_image_textures.max(1);
+        let mut held = self.recovered_images.iter().filter(|r| r.texture.is_some()).count();
+
+        for (i, recovered) in self.recovered_images.iter_mut().enumerate() {
+            if held <= limit {
+                break;
+            }
+            if recovered.texture.take().is_some() {
+                held -= 1;
+                self.decode_logs.push(format!(
+                    "-> Freed the texture of segment #{} ({}x{} at offset 0x{:X}), over the limit of {}",
+                    i + 1,
+                    recovered.size[0],
+                    recovered.size[1],
+                    recovered.offset,
+                    limit
+                ));
+            }
+        }
+    }
+
+    // RGBA on the GPU, so 4 bytes per pixel of every texture still held
+    fn texture_memory_estimate(&self) -> usize {
+        self.recovered_images
+            .iter()
+            .filter(|r| r.texture.is_some())
+            .map(|r| r.size[0] * r.size[1] * 4)
+            .sum()
+    }
+
+    // The page as the decode sees it, to paste into another tool
+    fn copy_page_base64(&mut self, ctx: &egui::Context) {
+        let clean = decode::clean_page(&self.text_content, self.alphabet, &self.cleaning_filter);
+        self.status_msg = format!("Copied {} Base64 chars", clean.len());
+        ctx.copy_text(clean);
+    }
+
+    // Decodes just this page, so the bytes are only aligned if the earlier pages are
+    fn copy_page_hex(&mut self, ctx: &egui::Context) {
+        let clean = decode::clean_page(&self.text_content, self.alphabet, &self.cleaning_filter);
+        match decode::decode_clean_base64(&clean, self.alphabet) {
+            Ok(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                self.status_msg = format!("Copied {} bytes as hex", bytes.len());
+                ctx.copy_text(hex);
+            }
+            Err(e) => self.status_msg = format!("Page doesn't decode: {}", e),
+        }
+    }
+
+    // Appends the logs of the last decode to decode_log.txt, with the settings that produced them
+    fn export_decode_log(&mut self) {
+        let path = self.workdir.join("decode_log.txt");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let alphabet = self.alphabet.name();
+
+        let mut content = format!(
+            "=== Decode log exported {} ===\n\
+             Alphabet: {}\nStrip chars: {:?}\nTrim: {} {} from the start, {} from the end\nPer page: {}\nSubfolders: {}\nDecompress: {}\nAll alignments: {}\nLine length: {}\nKeep carriage returns: {}\n\n",
+            format_utc(now),
+            alphabet,
+            self.cleaning_filter.strip_chars,
+            self.cleaning_filter.trim_start,
+            if self.cleaning_filter.trim_unit == TrimUnit::Lines { "lines" } else { "chars" },
+            self.cleaning_filter.trim_end,
+            self.per_page_decode,
+            self.recursive_scan,
+            self.decompress_stream,
+            self.try_alignments,
+            self.settings.line_length,
+            self.settings.keep_carriage_returns
+        );
+        for line in &self.decode_logs {
+            content.push_str(line);
+            content.push('\n');
+        }
+        content.push('\n');
+
+        let result = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()));
+        self.status_msg = match result {
+            Ok(_) => format!("Decode log appended to {}", path.display()),
+            Err(e) => format!("Error writing {}: {}", path.display(), e),
+        };
+    }
+
+    // All page files in decode order in one combined.txt, for a script that wants a single input.
+    // The current page is saved first so the file matches the editor.
+    fn export_combined_text(&mut self) {
+        if self.text_dirty {
+            self.save_page();
+        }
+
+        let files = list_page_files(&self.workdir, self.recursive_scan, &PageFilePattern::of(&self.settings));
+        let mut combined = String::new();
+        for file in &files {
+            match fs::read_to_string(file) {
+                Ok(content) => {
+                    combined.push_str(&content);
+                    // Pages must not run into each other, unless it's all one line anyway
+                    if !combined.ends_with('\n') {
+                        combined.push('\n');
+                    }
+                }
+                Err(e) => self.decode_logs.push(format!("Error reading {}: {}", file.display(), e)),
+            }
+        }
+        if self.combined_one_line {
+            combined.retain(|c| c != '\n' && c != '\r');
+        }
+
+        let path = self.workdir.join(COMBINED_FILE_NAME);
+        self.status_msg = match fs::write(&path, &combined) {
+            Ok(_) => format!(
+                "Wrote {} characters of {} page files to {}",
+                format_thousands(combined.chars().count()),
+                files.len(),
+                path.display()
+            ),
+            Err(e) => format!("Error writing {}: {}", path.display(), e),
+        };
+        self.decode_logs.push(self.status_msg.clone());
+    }
+
+    // Writes the original bytes of every recovered image, no re-encoding
+    fn save_recovered_images(&mut self) {
+        if self.recovered_images.is_empty() {
+            self.decode_logs.push("No recovered images to save, run a decode first".into());
+            return;
+        }
+
+        for (i, recovered) in self.recovered_images.iter().enumerate() {
+            let path = recovered_image_path(&self.workdir, i + 1, recovered.format);
+
+            match fs::write(&path, &recovered.bytes) {
+                Ok(_) => self.decode_logs.push(format!("Saved {}", path.display())),
+                Err(e) => self.decode_logs.push(format!("Error saving {}: {}", path.display(), e)),
+            }
+        }
+    }
+
+    fn perform_hex_jump(&mut self, ctx: &egui::Context) {
+        // 1. Parse Hex (or Decimal) Input
+        let (radix, base_name) = if self.hex_jump_decimal { (10, "Decimal") } else { (16, "Hex") };
+        let clean_input = self.hex_input.trim().trim_start_matches("0x").to_string();
+        let binary_offset = match u64::from_str_radix(&clean_input, radix) {
+            Ok(val) => val,
+            Err(_) => {
+                self.jump_status_msg = format!("Invalid {}", if self.hex_jump_decimal { "Decimal" } else { "Hexadecimal" });
+                return;
+            }
+        };
+
+        // 2. Calculate Target Base64 Index
+        // Rule: 3 bytes of binary = 4 bytes of Base64 (or 1 byte = 2 hex digits).
+        // Formula: (Offset / 3) * 4
+        let target_b64_index = self.alphabet.char_index(binary_offset);
+        
+        self.jump_status_msg = format!(
+            "Seeking {} {} (0x{:X}) -> Base64 Index {}",
+            base_name, clean_input, binary_offset, target_b64_index
+        );
+
+        // 3. Iterate Files
+        let mut current_b64_count: u64 = 0;
+        
+        // Same files, in the same order, as the stream decoding
+        let pattern = PageFilePattern::of(&self.settings);
+        let files = list_page_files(&self.workdir, self.recursive_scan, &pattern);
+
+        let mut found_file = None;
+        let mut found_cursor_pos = 0;
+        let mut found_char = ' ';
+        let mut skipped_chars = 0; // Non Base64 chars (other than line breaks) right before the target
+
+        for file in files.iter() {
+            let Ok(content) = fs::read_to_string(file) else {
+                continue;
+            };
+            // Only the chars the decode keeps count: trimmed and stripped ones are invisible,
+            // like newlines and spaces
+            let positions = decode::clean_page_positions(&content, self.alphabet, &self.cleaning_filter);
+            let on_file = positions.len() as u64;
+            if target_b64_index < current_b64_count + on_file {
+                // FOUND IT!
+                let k = (target_b64_index - current_b64_count) as usize;
+                // Chars between the previous kept one (or the trimmed start) and the target
+                let from = if k > 0 { positions[k - 1] + 1 } else { self.cleaning_filter.trim(&content).0 };
+                let chars: Vec<char> = content.chars().collect();
+
+                found_file = Some(file);
+                found_cursor_pos = positions[k];
+                found_char = chars[positions[k]];
+                skipped_chars = chars[from..positions[k]].iter().filter(|&&c| c != '\n' && c != '\r').count();
+                break;
+            }
+            current_b64_count += on_file;
+        }
+
+        // 4. Act on Result
+        let Some(file) = found_file else {
+            self.jump_status_msg = format!("Offset out of bounds. Max Base64 len: {}", current_b64_count);
+            return;
+        };
+        // Only the numbered files of the workdir are pages of the open documents (like
+        // count_base64_before_page), a subfolder's page003.txt is not page 3.
+        // PDF pages are 0-indexed, File names are usually 1-indexed
+        let page_index = pattern
+            .number_of(file)
+            .filter(|_| file.parent() == Some(self.workdir.as_path()))
+            .map(|page_num| page_num.saturating_sub(1))
+            .filter(|&index| index < self.total_pages);
+        let Some(idx) = page_index else {
+            self.jump_status_msg = format!(
+                "{} offset {} is in {}, Char {}: '{}', not a page of the open document",
+                base_name,
+                clean_input,
+                file.strip_prefix(&self.workdir).unwrap_or(file).display(),
+                found_cursor_pos,
+                found_char
+            );
+            return;
+        };
+
+        if !self.can_leave_page(idx) {
+            self.jump_status_msg = format!("Page {} has unsaved edits, save or discard them first", self.current_page_index + 1);
+            return;
+        }
+        // Load the page, unless it is this one: that would throw away unsaved edits
+        if idx != self.current_page_index {
+            self.load_page(idx);
+        }
+        self.jump_status_msg = format!(
+            "{} offset {} found on Page {}, Char {}: '{}'",
+            base_name,
+            clean_input,
+            idx + 1,
+            found_cursor_pos,
+            found_char
+        );
+        if skipped_chars > 0 {
+            self.jump_status_msg.push_str(&format!(
+                ", snapped past {} invalid char(s) to the next valid position",
+                skipped_chars
+            ));
+        }
+        self.show_hex_dialog = false; // Close dialog
+        self.status_msg = self.jump_status_msg.clone(); // The dialog is gone, show it in the toolbar
+
+        // Set Cursor and Focus
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+            state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
+                egui::text::CCursor::new(found_cursor_pos)
+            )));
+            state.store(ctx, text_id);
+            ctx.memory_mut(|m| m.request_focus(text_id));
+        }
+    }
+
+    // Replaces `find` in `text`, returning the new text and how many replacements were made.
+    // A whole word match must not touch another letter, digit or underscore on either side.
+    fn replace_text(text: &str, find: &str, replacement: &str, whole_word: bool) -> (String, usize) {
+        if find.is_empty() {
+            return (text.to_string(), 0);
+        }
+
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let mut result = String::with_capacity(text.len());
+        let mut count = 0;
+        let mut last_end = 0;
+
+        for (start, matched) in text.match_indices(find) {
+            let end = start + matched.len();
+            if whole_word {
+                let before = text[..start].chars().next_back();
+                let after = text[end..].chars().next();
+                if before.is_some_and(is_word_char) || after.is_some_and(is_word_char) {
+                    continue;
+                }
+            }
+            result.push_str(&text[last_end..start]);
+            result.push_str(replacement);
+            last_end = end;
+            count += 1;
+        }
+        result.push_str(&text[last_end..]);
+
+        (result, count)
+    }
+
+    fn perform_replace(&mut self) {
+        let (new_text, mut total) = Self::replace_text(
+            &self.text_content,
+            &self.find_input,
+            &self.replace_input,
+            self.replace_whole_word,
+        );
+        // The change is picked up by track_history, which marks the page dirty
+        self.text_content = new_text;
+        let mut files_changed = 0;
+        let mut failed = Vec::new();
+
+        if self.replace_all_pages {
+            for index in (0..self.total_pages).filter(|&i| i != self.current_page_index) {
+                let path = self.page_file_path(index);
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+
+                let (new_content, count) = Self::replace_text(
+                    &content,
+                    &self.find_input,
+                    &self.replace_input,
+                    self.replace_whole_word,
+                );
+                if count > 0 {
+                    // Saved with the line breaks of the Settings, like save_page
+                    match fs::write(&path, self.settings.line_ending.normalize(&new_content)) {
+                        Ok(_) => {
+                            total += count;
+                            files_changed += 1;
+                        }
+                        Err(e) => failed.push(format!("{} ({})", path.display(), e)),
+                    }
+                }
+            }
+            self.base64_before_page = self.count_base64_before_page(self.current_page_index);
+            self.clear_page_validity();
+        }
+
+        self.replace_status_msg = if self.replace_all_pages {
+            format!("Replaced {} occurrence(s), {} other file(s) changed", total, files_changed)
+        } else {
+            format!("Replaced {} occurrence(s)", total)
+        };
+        if !failed.is_empty() {
+            self.status_msg = format!("Could not save the replacements in {}", failed.join(", "));
+        }
+    }
+
+    // Char index of every occurrence of the find bar query in the page
+    fn find_matches(&self) -> Vec<usize> {
+        if self.find_bar_query.is_empty() {
+            return Vec::new();
+        }
+        self.text_content
+            .match_indices(self.find_bar_query.as_str())
+            .map(|(byte_idx, _)| self.text_content[..byte_idx].chars().count())
+            .collect()
+    }
+
+    // Selects the next (or previous) match in the editor, wrapping around at the ends
+    fn find_in_page(&mut self, ctx: &egui::Context, backwards: bool) {
+        let matches = self.find_matches();
+        if matches.is_empty() {
+            self.find_bar_current = None;
+            return;
+        }
+
+        let current = match (self.find_bar_current, backwards) {
+            (Some(current), false) => (current + 1) % matches.len(),
+            (Some(current), true) => (current + matches.len() - 1) % matches.len(),
+            (None, false) => 0,
+            (None, true) => matches.len() - 1,
+        };
+        self.find_bar_current = Some(current);
+
+        // Select the match, the PDF view underlines the selection
+        let start = matches[current];
+        let end = start + self.find_bar_query.chars().count();
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+            state.cursor.set_char_range(Some(egui::text::CCursorRange::two(
+                egui::text::CCursor::new(start),
+                egui::text::CCursor::new(end),
+            )));
+            state.store(ctx, text_id);
+        }
+        self.scroll_to_cursor = true;
+    }
+
+    fn load_bookmarks(&mut self) {
+        self.bookmarks = fs::read_to_string(self.workdir.join(BOOKMARKS_FILE_NAME))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.trim().parse::<u16>().ok())
+            .filter(|&page| page > 0)
+            .map(|page| page - 1)
+            .collect();
+    }
+
+    // Written on every change, there are only a few of them
+    fn save_bookmarks(&mut self) {
+        let path = self.workdir.join(BOOKMARKS_FILE_NAME);
+        let mut pages: Vec<u16> = self.bookmarks.iter().copied().collect();
+        pages.sort_unstable();
+        let content: String = pages.iter().map(|index| format!("{}\n", index + 1)).collect();
+        if let Err(e) = fs::write(&path, content) {
+            self.status_msg = format!("Error saving bookmarks to {}: {}", path.display(), e);
+        }
+    }
+
+    fn toggle_bookmark(&mut self) {
+        let index = self.current_page_index;
+        if self.bookmarks.remove(&index) {
+            self.status_msg = format!("Removed the bookmark of page {}", index + 1);
+        } else {
+            self.bookmarks.insert(index);
+            self.status_msg = format!("Bookmarked page {}", index + 1);
+        }
+        self.save_bookmarks();
+    }
+
+    // The next (or previous) bookmarked page, wrapping around at the end of the document
+    fn jump_to_bookmark(&mut self, forward: bool) {
+        let mut pages: Vec<u16> = self.bookmarks.iter().copied().filter(|&index| index < self.total_pages).collect();
+        pages.sort_unstable();
+        let current = self.current_page_index;
+        let target = if forward {
+            pages.iter().find(|&&index| index > current).or(pages.first())
+        } else {
+            pages.iter().rev().find(|&&index| index < current).or(pages.last())
+        };
+        match target {
+            Some(&index) if index != current => self.navigate_to_page(index),
+            Some(_) => self.status_msg = "No other bookmarked page".to_string(),
+            None => self.status_msg = "No bookmarked pages, Ctrl+B bookmarks the current one".to_string(),
+        }
+    }
+
+    fn perform_goto_page(&mut self) {
+        let page_num = match self.goto_page_input.trim().parse::<u32>() {
+            Ok(val) => val,
+            Err(_) => {
+                self.goto_page_msg = "Invalid page number".to_string();
+                return;
+            }
+        };
+
+        if self.total_pages == 0 {
+            self.goto_page_msg = "No document loaded".to_string();
+            return;
+        }
+
+        // Pages are 1-based for humans, clamp into 0..total_pages
+        let max_page = self.total_pages as u32;
+        if page_num < 1 || page_num > max_page {
+            self.goto_page_msg = format!("Page {} out of range (1-{})", page_num, max_page);
+        } else {
+            self.goto_page_msg.clear();
+        }
+        let index = page_num.clamp(1, max_page) - 1;
+
+        self.navigate_to_page(index as u16);
+        self.goto_page_input = format!("{}", index + 1);
+    }
+
+    fn cycle_common_problem(&mut self, state: egui::text_edit::TextEditState) {
+        if let Some(range) = state.cursor.char_range() {
+            let idx = range.primary.index;
+            let current_char = self.text_content.chars().nth(idx).unwrap_or('*');
+            let next_char = self.text_content.chars().nth(idx + 1).unwrap_or('*');
+            let third_char = self.text_content.chars().nth(idx + 2).unwrap_or('*');
+
+            if let Some(replacement) = match (current_char, next_char, third_char) {
+                (' ', '1', ' ') => Some((3, "1")),
+                (' ', 'I', ' ') => Some((3, "1")),
+                (' ', 'l', ' ') => Some((3, "1")),
+                ('-', 'F', _) => Some((2, "+")),
+                ('r', 'n', _) => Some((2, "m")),
+                ('I', 'c', _) => Some((2, "k")),
+                ('I', 'C', _) => Some((2, "K")),
+                ('3', 'C', _) => Some((2, "X")),
+                ('I', 'J', _) => Some((2, "U")),
+                ('m', _, _) => Some((1, "rn")),
+                ('I', _, _) => Some((1, "l")),
+                ('l', _, _) => Some((1, "1")),
+                ('1', _, _) => Some((1, "I")),
+                ('O', _, _) => Some((1, "0")),
+                ('0', _, _) => Some((1, "O")),
+                ('g', _, _) => Some((1, "q")),
+                ('q', _, _) => Some((1, "g")),
+                ('c', _, _) => Some((1, "e")),
+                ('e', _, _) => Some((1, "c")),
+                ('Y', _, _) => Some((1, "V")),
+                ('V', _, _) => Some((1, "Y")),
+                _ => None
+            } {
+                // idx is a char index, lookalikes earlier on the page take more than one byte
+                let byte_at = |char_idx: usize| {
+                    self.text_content.char_indices().nth(char_idx).map_or(self.text_content.len(), |(i, _)| i)
+                };
+                let (start, end) = (byte_at(idx), byte_at(idx + replacement.0));
+                self.text_content.replace_range(start..end, replacement.1);
+            }
+        }
+    }
+
+    // Inserts the placeholder char at the cursor, for a char OCR dropped whose value isn't known yet.
+    // The line gets its length back and the position is kept, to come back to it.
+    fn insert_placeholder(&mut self, ctx: &egui::Context) {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        let Some(idx) = egui::text_edit::TextEditState::load(ctx, text_id)
+            .and_then(|state| state.cursor.char_range())
+            .map(|r| r.primary.index)
+        else {
+            self.status_msg = "Put the cursor where the character is missing first".to_string();
+            return;
+        };
+
+        self.sync_placeholders();
+        let byte_idx = self.text_content.char_indices().nth(idx).map_or(self.text_content.len(), |(i, _)| i);
+        self.text_content.insert(byte_idx, self.settings.placeholder_char);
+        // We know the edit, no need to guess it like sync_placeholders
+        for position in self.placeholders.iter_mut().filter(|position| **position >= idx) {
+            *position += 1;
+        }
+        self.placeholders.push(idx);
+        self.placeholders.sort_unstable();
+        self.placeholder_snapshot = self.text_content.clone();
+
+        Self::move_cursor_to(ctx, idx + 1);
+        self.status_msg = format!(
+            "Placeholder '{}' inserted, {} on this page",
+            self.settings.placeholder_char,
+            self.placeholders.len()
+        );
+    }
+
+    // Called every frame: moves the placeholders along with the edits since the last call.
+    // One that was typed over is gone, its char is known now.
+    fn sync_placeholders(&mut self) {
+        if self.text_content != self.placeholder_snapshot {
+            diff::shift_positions(&self.placeholder_snapshot, &self.text_content, &mut self.placeholders);
+            self.placeholder_snapshot = self.text_content.clone();
+        }
+    }
+
+    // 0-based (line, column) of a char index, for listing positions
+    fn line_and_column(text: &str, char_idx: usize) -> (usize, usize) {
+        let before: Vec<char> = text.chars().take(char_idx).collect();
+        let line = before.iter().filter(|&&c| c == '\n').count();
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count();
+        (line, column)
+    }
+
+    // Called every frame: if the text changed since the last snapshot, remember the old version
+    fn track_history(&mut self) {
+        if self.text_content != self.history_snapshot {
+            let previous = std::mem::replace(&mut self.history_snapshot, self.text_content.clone());
+            self.undo_stack.push(previous);
+            if self.undo_stack.len() > UNDO_HISTORY_SIZE {
+                self.undo_stack.remove(0);
+            }
+            self.redo_stack.clear();
+            self.text_dirty = true;
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(previous) = self.undo_stack.pop() {
+            let current = std::mem::replace(&mut self.text_content, previous);
+            self.redo_stack.push(current);
+            self.history_snapshot = self.text_content.clone();
+            self.text_dirty = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(next) = self.redo_stack.pop() {
+            let current = std::mem::replace(&mut self.text_content, next);
+            self.undo_stack.push(current);
+            self.history_snapshot = self.text_content.clone();
+            self.text_dirty = true;
+        }
+    }
+
+    // Base64 chars on the saved files of all pages before the current one, cleaned like the decode.
+    // Same files in the same order as the stream: the numbered files of the workdir come first,
+    // subfolders and odd names like page.txt after every page.
+    fn count_base64_before_page(&self, index: u16) -> u64 {
+        let pattern = PageFilePattern::of(&self.settings);
+        list_page_files(&self.workdir, self.recursive_scan, &pattern)
+            .iter()
+            .take_while(|file| {
+                file.parent() == Some(self.workdir.as_path()) && pattern.number_of(file).is_some_and(|number| number <= index)
+            })
+            .filter_map(|file| fs::read_to_string(file).ok())
+            .map(|content| decode::clean_page(&content, self.alphabet, &self.cleaning_filter).len() as u64)
+            .sum()
+    }
+
+    // Inverse of perform_hex_jump: binary offset of the byte the cursor char contributes to
+    fn get_cursor_binary_offset(&self, ctx: &egui::Context) -> Option<u64> {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        let state = egui::text_edit::TextEditState::load(ctx, text_id)?;
+        let char_idx = state.cursor.char_range()?.primary.index;
+
+        // Cleaned like the decode, so trimmed and stripped chars don't count
+        let on_page = decode::clean_page_positions(&self.text_content, self.alphabet, &self.cleaning_filter)
+            .iter()
+            .take_while(|&&i| i < char_idx)
+            .count() as u64;
+        let b64_index = self.base64_before_page + on_page;
+
+        // 4 chars of Base64 = 3 bytes, each char carries 6 bits (hex: 2 digits = 1 byte)
+        Some(self.alphabet.binary_offset(b64_index))
+    }
+
+    // The rendered page with zoom, pan and the selection highlights
+    fn show_pdf_view(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, text_id: egui::Id, height: f32) {
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .max_height(height)
+            .min_scrolled_height(height)
+            .id_salt("pdf_scroll");
+
+        // Side by side: follow the top line of the editor
+        if let (Some(top_fraction), Some(texture)) = (self.pdf_sync_fraction.take(), &self.page_texture) {
+            let size = texture.size_vec2();
+            let display_height = size.y * ui.available_width() / size.x;
+            let offset = self.pan.y + top_fraction * display_height * self.zoom;
+            scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
+        }
+
+        scroll_area.show(ui, |ui| {
+            if let Some(texture) = self.page_texture.clone() {
+                let size = texture.size_vec2();
+                let scale = ui.available_width() / size.x;
+                let display_size = size * scale;
+
+                let (rect, response) =
+                    ui.allocate_exact_size(display_size, egui::Sense::click_and_drag());
+
+                // Zoom (Ctrl+scroll or pinch) around the mouse pointer
+                if let Some(pointer) = response.hover_pos() {
+                    let zoom_delta = ui.input(|i| i.zoom_delta());
+                    if zoom_delta != 1.0 {
+                        let new_zoom = (self.zoom * zoom_delta).clamp(1.0, 20.0);
+                        // Keep the image point under the pointer in place
+                        let anchor = (pointer - rect.min - self.pan) / self.zoom;
+                        self.pan = pointer - rect.min - anchor * new_zoom;
+                        self.zoom = new_zoom;
+                    }
+                }
+                // Pan by dragging, double click resets
+                if response.dragged() {
+                    self.pan += response.drag_delta();
+                }
+                if response.double_clicked() {
+                    self.zoom = 1.0;
+                    self.pan = egui::Vec2::ZERO;
+                }
+                // Don't let the image be dragged out of the view
+                self.pan = self
+                    .pan
+                    .clamp(display_size - display_size * self.zoom, egui::Vec2::ZERO);
+
+                let image_rect =
+                    egui::Rect::from_min_size(rect.min + self.pan, display_size * self.zoom);
+                let image_size = image_rect.size();
+
+                let painter = ui.painter_at(rect);
+                painter.image(
+                    texture.id(),
+                    image_rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+
+                if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+                    if let Some(range) = state.cursor.char_range() {
+                        let highlights = self.get_highlights(range);
+
+                        if let Some(first_rect) = highlights.first() {
+                            let screen_min = image_rect.min
+                                + egui::vec2(
+                                    first_rect.min.x * image_size.x,
+                                    first_rect.min.y * image_size.y,
+                                );
+                            let screen_max = image_rect.min
+                                + egui::vec2(
+                                    first_rect.max.x * image_size.x,
+                                    first_rect.max.y * image_size.y,
+                                );
+                            let cursor_screen_rect =
+                                egui::Rect::from_min_max(screen_min, screen_max);
+
+                            // Tell Egui to scroll here if it's off-screen
+                            // None = Minimal scroll (just bring it into view)
+                            // Some(Align::Center) = Always center it
+                            // (side by side the view follows the editor scrolling instead)
+                            if !self.side_by_side {
+                                ui.scroll_to_rect(cursor_screen_rect, None);
+                            }
+                        }
+
+                        let [r, g, b] = self.settings.highlight_color;
+                        let color = egui::Color32::from_rgb(r, g, b);
+                        for h_rect_norm in highlights {
+                            self.paint_highlight(&painter, image_rect, h_rect_norm, color);
+                        }
+                    }
+                }
+
+                // Ctrl+click ranges, each in its own color
+                for (i, range) in self.pinned_selections.iter().enumerate() {
+                    let color = PINNED_HIGHLIGHT_PALETTE[i % PINNED_HIGHLIGHT_PALETTE.len()];
+                    for h_rect_norm in self.get_highlights(*range) {
+                        self.paint_highlight(&painter, image_rect, h_rect_norm, color);
+                    }
+                }
+            } else if let Some(index) = self.rendering_page {
+                ui.add_sized(
+                    [ui.available_width(), height],
+                    egui::Label::new(format!("Rendering page {}…", index + 1)),
+                );
+            }
+        });
+    }
+
+    // `h_rect_norm` is relative to the page (0..1), as returned by get_highlights
+    fn paint_highlight(
+        &self,
+        painter: &egui::Painter,
+        image_rect: egui::Rect,
+        h_rect_norm: egui::Rect,
+        color: egui::Color32,
+    ) {
+        let image_size = image_rect.size();
+        // Convert normalized coordinates (0..1) back to Screen Pixels
+        let screen_min = image_rect.min
+            + egui::vec2(
+                h_rect_norm.min.x * image_size.x,
+                h_rect_norm.min.y * image_size.y,
+            );
+        let screen_max = image_rect.min
+            + egui::vec2(
+                h_rect_norm.max.x * image_size.x,
+                h_rect_norm.max.y * image_size.y,
+            );
+
+        let stroke = egui::Stroke::new(self.settings.highlight_width, color);
+
+        match self.settings.highlight_style {
+            HighlightStyle::Outline => {
+                let screen_rect = egui::Rect::from_min_max(screen_min, screen_max);
+
+                painter.rect_stroke(screen_rect, 0.0, stroke, egui::StrokeKind::Outside);
+            }
+            HighlightStyle::Underline => {
+                let line_y = screen_max.y; // Bottom of the rectangle
+                let line_start = egui::pos2(screen_min.x - 2.0, line_y); // Extend slightly to the left
+                let line_end = egui::pos2(screen_max.x + 2.0, line_y);   // Extend slightly to the right
+
+                // Draw a bold line under the letter
+                painter.line_segment([line_start, line_end], stroke);
+            }
+        }
+    }
+
+    fn show_find_bar(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        if self.show_find_bar {
+            ui.horizontal(|ui| {
+                ui.label("Find:");
+                let find_id = egui::Id::new("find_bar_input");
+                let response = ui.add(egui::TextEdit::singleline(&mut self.find_bar_query).id(find_id));
+                if response.changed() {
+                    self.find_bar_current = None;
+                }
+                if self.focus_find_bar {
+                    response.request_focus();
+                    self.focus_find_bar = false;
+                }
+
+                // Enter: next, Shift+Enter: previous. Keep the focus for the next Enter.
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    let backwards = ui.input(|i| i.modifiers.shift);
+                    self.find_in_page(ctx, backwards);
+                    response.request_focus();
+                }
+                if ui.button("Prev").clicked() {
+                    self.find_in_page(ctx, true);
+                }
+                if ui.button("Next").clicked() {
+                    self.find_in_page(ctx, false);
+                }
+
+                let match_count = self.find_matches().len();
+                match self.find_bar_current {
+                    Some(current) if current < match_count => {
+                        ui.label(format!("{} of {}", current + 1, match_count));
+                    }
+                    _ if !self.find_bar_query.is_empty() && match_count == 0 => {
+                        ui.colored_label(egui::Color32::RED, "No matches");
+                    }
+                    _ if match_count > 0 => {
+                        ui.label(format!("{} matches", match_count));
+                    }
+                    _ => {}
+                }
+
+                if ui.button("✖").clicked() {
+                    self.show_find_bar = false;
+                }
+            });
+        }
+    }
+
+    // The margin takes its row height from the same font, so it follows on the next frame
+    fn set_editor_font_size(&mut self, size: f32) {
+        let size = size.clamp(*EDITOR_FONT_SIZE_RANGE.start(), *EDITOR_FONT_SIZE_RANGE.end());
+        if size != self.settings.editor_font_size {
+            self.settings.editor_font_size = size;
+            self.settings.save();
+        }
+    }
+
+    // Line numbers, line indicators and the text editor itself
+    fn show_editor(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, text_id: egui::Id, height: f32) {
+        let output = egui::ScrollArea::vertical()
+            .id_salt("text_scroll")
+            .max_height(height)
+            .min_scrolled_height(height)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    // SETUP FONTS
+                    let font_size = self.settings.editor_font_size;
+                    // We define the font here so we can use metrics for both the indicator and the editor
+                    let font_id = egui::FontId::new(font_size, egui::FontFamily::Monospace);
+                    let row_height = ui.fonts_mut(|f| f.row_height(&font_id)) * 1.015;
+
+                    // 1. LEFT PANEL: LINE NUMBERS AND STATUS INDICATORS
+                    // We allocate a vertical strip. Width = line numbers + 15px.
+                    // Height = total lines * row height.
+                    let total_lines = self.text_content.lines().count().max(1);
+                    let desired_height = total_lines as f32 * row_height;
+                    let digit_width = ui.fonts_mut(|f| f.glyph_width(&font_id, '0'));
+                    let numbers_width = total_lines.to_string().len() as f32 * digit_width + 6.0;
+
+                    // Allocate space for the indicators
+                    // Clicking an indicator moves the cursor to that line
+                    let (rect, response) = ui.allocate_exact_size(
+                        egui::vec2(numbers_width + 15.0, desired_height),
+                        egui::Sense::click(),
+                    );
+                    let response = response.on_hover_cursor(egui::CursorIcon::PointingHand);
+
+                    // Draw the indicators
+                    let painter = ui.painter_at(rect);
+                    for (i, line) in self.text_content.lines().enumerate() {
+                        let color = LineStatus::of(line, self.alphabet, self.settings.line_length).color(ui.visuals().dark_mode);
+
+                        // Calculate position
+                        // Note: TextEdit usually adds a small margin (approx 4.0-8.0px).
+                        // We offset Y slightly to align with the text baseline.
+                        let y_offset = rect.top() + (i as f32 * row_height) + 4.0;
+
+                        // 1-based, right-aligned against the indicator
+                        painter.text(
+                            egui::pos2(rect.left() + numbers_width - 6.0, y_offset),
+                            egui::Align2::RIGHT_TOP,
+                            (i + 1).to_string(),
+                            font_id.clone(),
+                            egui::Color32::GRAY,
+                        );
+
+                        painter.rect_filled(
+                            egui::Rect::from_min_size(
+                                egui::pos2(rect.left() + numbers_width, y_offset),
+                                egui::vec2(8.0, row_height - 2.0),
+                            ),
+                            2.0, // rounding
+                            color,
+                        );
+                    }
+
+                    if response.clicked() {
+                        if let Some(pos) = response.interact_pointer_pos() {
+                            let line_idx = ((pos.y - rect.top() - 4.0) / row_height).max(0.0) as usize;
+                            Self::move_cursor_to(ctx, Self::line_start_char_index(&self.text_content, line_idx));
+                        }
+                    }
+
+                    // Exact character count of the hovered line
+                    if let Some(pos) = response.hover_pos() {
+                        let line_idx = ((pos.y - rect.top() - 4.0) / row_height).max(0.0) as usize;
+                        if let Some(line) = self.text_content.lines().nth(line_idx) {
+                            response.on_hover_text(self.line_tooltip(line_idx, line));
+                        }
+                    }
+
+                    if ctx.input(|i| i.key_pressed(egui::Key::Space) && i.modifiers.ctrl) {
+                        if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+                            self.cycle_common_problem(state);
+                        }
+                    }
+                    if ctx.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.ctrl) {
+                        self.adjust_line_spaces_to_pdf(ctx);
+                    }
+                    if ctx.input(|i| i.key_pressed(egui::Key::I) && i.modifiers.ctrl) {
+                        self.insert_placeholder(ctx);
+                    }
+
+                    // Undo/Redo. We consume the keys so the TextEdit's own undoer does not
+                    // fight with ours (it doesn't know about Ctrl+Space and Ctrl+Enter edits)
+                    let redo_pressed = ctx.input_mut(|i| {
+                        i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                            || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)
+                    });
+                    let undo_pressed = ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z));
+                    if redo_pressed {
+                        self.redo();
+                    } else if undo_pressed {
+                        self.undo();
+                    }
+
+                    let text_edit = egui::TextEdit::multiline(&mut self.text_content)
+                        .id(text_id)
+                        .desired_width(f32::INFINITY)
+                        .horizontal_align(emath::Align::Center)
+                        .font(egui::FontId::new(font_size, egui::FontFamily::Monospace));
+
+                    let text_edit_response = text_edit.show(ui);
+                    // Ctrl+click (or Ctrl+drag) keeps the selection highlighted on the page
+                    let pin_clicked = text_edit_response.response.clicked() || text_edit_response.response.drag_stopped();
+                    if pin_clicked && ctx.input(|i| i.modifiers.ctrl) {
+                        if let Some(range) = text_edit_response.state.cursor.char_range() {
+                            self.pinned_selections.push(range);
+                        }
+                    }
+                    // This is an attempt to keep cursor visible in the text editor
+                    // (also when the find bar moved it while the editor has no focus)
+                    if text_edit_response.response.has_focus() || self.scroll_to_cursor {
+                        self.scroll_to_cursor = false;
+                        if let Some(cursor_range) = text_edit_response.state.cursor.char_range() {
+                            let cursor_relative_rect = text_edit_response.galley.pos_from_cursor(cursor_range.primary);
+                            
+                            let cursor_screen_rect = cursor_relative_rect.translate(text_edit_response.galley_pos.to_vec2());
+                            
+                            ui.scroll_to_rect(cursor_screen_rect, None);
+                        }                        
+                    }
+
+                    // Same details as the margin, for the line under the pointer
+                    if let Some(pos) = text_edit_response.response.hover_pos() {
+                        let cursor = text_edit_response.galley.cursor_from_pos(pos - text_edit_response.galley_pos);
+                        let line_idx = self.text_content.chars().take(cursor.index).filter(|&c| c == '\n').count();
+                        if let Some(line) = self.text_content.lines().nth(line_idx) {
+                            text_edit_response.response.clone().on_hover_text(self.line_tooltip(line_idx, line));
+                        }
+                    }
+
+                    self.track_history();
+                    self.sync_placeholders();
+                    row_height
+                })
+                .inner
+            });
+
+        // Ctrl+scroll over the editor changes the font size (over the PDF view it zooms the page)
+        if ui.rect_contains_pointer(output.inner_rect) {
+            let zoom_delta = ctx.input(|i| i.zoom_delta());
+            if zoom_delta != 1.0 {
+                self.set_editor_font_size(self.settings.editor_font_size * zoom_delta);
+            }
+        }
+
+        // Side by side: scroll the page to the top visible line of the editor
+        if self.side_by_side {
+            let row_height = output.inner;
+            let top_line = (output.state.offset.y / row_height) as usize;
+            if self.synced_top_line != Some(top_line) {
+                self.synced_top_line = Some(top_line);
+                let char_idx = Self::line_start_char_index(&self.text_content, top_line);
+                let range = egui::text::CCursorRange::one(egui::text::CCursor::new(char_idx));
+                if let Some(char_rect) = self.get_highlights(range).first() {
+                    self.pdf_sync_fraction = Some(char_rect.min.y);
+                    ctx.request_repaint();
+                }
+            }
+        }
+    }
+
+    // Offset, 16 bytes as hex and as ASCII per row, image signatures stand out.
+    // Only the visible rows are laid out, the stream can be megabytes.
+    fn show_hex_dump(&self, ui: &mut egui::Ui) {
+        const BYTES_PER_ROW: usize = 16;
+
+        if self.decoded_bytes.is_empty() {
+            ui.label("Nothing decoded yet.");
+            return;
+        }
+
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let row_height = ui.fonts_mut(|f| f.row_height(&font_id));
+        let text_color = ui.visuals().text_color();
+        let dim_color = ui.visuals().weak_text_color();
+        let magic_color = ui.visuals().warn_fg_color;
+        let rows = self.decoded_bytes.len().div_ceil(BYTES_PER_ROW);
+
+        egui::ScrollArea::vertical()
+            .id_salt("hex_dump_scroll")
+            .max_height(300.0)
+            .show_rows(ui, row_height, rows, |ui, row_range| {
+                for row in row_range {
+                    let start = row * BYTES_PER_ROW;
+                    let chunk = &self.decoded_bytes[start..(start + BYTES_PER_ROW).min(self.decoded_bytes.len())];
+                    let is_magic = |offset: usize| {
+                        let i = self.decoded_magic.partition_point(|&(s, len)| s + len <= offset);
+                        self.decoded_magic.get(i).is_some_and(|&(s, _)| s <= offset)
+                    };
+
+                    let mut job = egui::text::LayoutJob::default();
+                    let mut append = |text: &str, color: egui::Color32| {
+                        job.append(text, 0.0, egui::TextFormat::simple(font_id.clone(), color));
+                    };
+                    append(&format!("{:08X}  ", start), dim_color);
+                    for i in 0..BYTES_PER_ROW {
+                        match chunk.get(i) {
+                            Some(b) => {
+                                let color = if is_magic(start + i) { magic_color } else { text_color };
+                                append(&format!("{:02X} ", b), color);
+                            }
+                            None => append("   ", text_color),
+                        }
+                    }
+                    append(" ", text_color);
+                    for (i, &b) in chunk.iter().enumerate() {
+                        let c = if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' };
+                        let color = if is_magic(start + i) { magic_color } else { dim_color };
+                        append(&c.to_string(), color);
+                    }
+                    ui.label(job);
+                }
+            });
+    }
+
+    // Char count, what is wrong with the line and the first bytes it decodes to
+    fn line_tooltip(&self, line_idx: usize, line: &str) -> String {
+        const SHOWN_INVALID: usize = 10;
+        const SHOWN_BYTES: usize = 12;
+
+        let mut tooltip = format!(
+            "Line {}: {} characters",
+            line_idx + 1,
+            line.trim().chars().count()
+        );
+
+        let invalid: Vec<String> = line
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| !c.is_whitespace() && !self.alphabet.valid_chars().contains(c))
+            .map(|(col, c)| format!("{} '{}'", col + 1, c))
+            .collect();
+        if !invalid.is_empty() {
+            tooltip.push_str(&format!(
+                "\nInvalid at column {}{}",
+                invalid.iter().take(SHOWN_INVALID).cloned().collect::<Vec<_>>().join(", "),
+                if invalid.len() > SHOWN_INVALID { ", ..." } else { "" }
+            ));
+        }
+        if let Some(lookalikes) = decode::describe_lookalikes(line) {
+            tooltip.push_str(&format!("\nLookalikes: {}", lookalikes));
+        }
+
+        // Decoded on its own, which lines up with the stream as long as lines are a multiple of 4
+        let mut clean = decode::clean_base64(line, self.alphabet, &self.cleaning_filter);
+        let group = self.alphabet.group_chars();
+        clean.truncate(clean.len() / group * group);
+        if let Ok(bytes) = decode::decode_clean_base64(&clean, self.alphabet) {
+            let hex: Vec<String> = bytes.iter().take(SHOWN_BYTES).map(|b| format!("{:02X}", b)).collect();
+            tooltip.push_str(&format!(
+                "\nBytes: {}{}",
+                hex.join(" "),
+                if bytes.len() > SHOWN_BYTES { " ..." } else { "" }
+            ));
+        }
+        tooltip
+    }
+
+    // A horizontal bar that can be dragged to change the height of the section above it
+    fn splitter(ui: &mut egui::Ui, fraction: &mut f32, total_height: f32) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), 8.0),
+            egui::Sense::drag(),
+        );
+        let response = response.on_hover_cursor(egui::CursorIcon::ResizeVertical);
+
+        let color = if response.hovered() || response.dragged() {
+            ui.visuals().widgets.hovered.fg_stroke.color
+        } else {
+            ui.visuals().widgets.noninteractive.bg_stroke.color
+        };
+        ui.painter()
+            .hline(rect.x_range(), rect.center().y, egui::Stroke::new(2.0, color));
+
+        if response.dragged() && total_height > 0.0 {
+            *fraction = (*fraction + response.drag_delta().y / total_height).clamp(0.05, 0.8);
+        }
+    }
+
+    fn get_current_line_number(&self, ctx: &egui::Context) -> String {
+        let text_id = egui::Id::new("shared_pdf_editor_id");
+        
+        if let Some(state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+            if let Some(range) = state.cursor.char_range() {
+                // Get the index of the cursor (primary selection point)
+                let char_idx = range.primary.index;
+
+                // Count newlines up to the cursor position
+                // We add 1 because humans count lines starting from 1, not 0
+                let line_count = self.text_content.chars()
+                    .take(char_idx)
+                    .filter(|&c| c == '\n')
+                    .count();
+
+                return format!("{}", line_count + 1);
+            }
+        }
+        "?".to_string()
+    }
+
+    // --display-script, then the one from Settings, then display_script.sh/.bat where we were launched
+    fn display_script_path(&self) -> PathBuf {
+        if let Some(script) = &self.display_script {
+            return script.clone();
+        }
+        if !self.settings.display_script.is_empty() {
+            return PathBuf::from(&self.settings.display_script);
+        }
+
+        // Determine script name based on OS
+        #[cfg(target_os = "windows")]
+        let script = "display_script.bat";
+        #[cfg(not(target_os = "windows"))]
+        let script = "./display_script.sh";
+
+        // The script lives where we were launched, but works on the page files
+        env::current_dir()
+            .map(|dir| dir.join(script))
+            .unwrap_or_else(|_| PathBuf::from(script))
+    }
+
+    // Runs the script on a thread of its own, what it prints ends up in the decode log
+    fn display_script(&mut self, ctx: &egui::Context) {
+        let script = self.display_script_path();
+        let workdir = self.workdir.clone();
+        self.decode_logs.push(format!("Running script: {} in {}", script.display(), workdir.display()));
+
+        let (tx, rx) = mpsc::channel();
+        self.script_rx = Some(rx);
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            // Execute the script
+            let output = if cfg!(target_os = "windows") {
+                Command::new("cmd").arg("/C").arg(&script).current_dir(&workdir).output()
+            } else {
+                Command::new("sh").arg(&script).current_dir(&workdir).output()
+            };
+            match output {
+                Ok(output) => {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    for line in stdout.lines().chain(stderr.lines()) {
+                        let _ = tx.send(format!("[script] {}", line));
+                    }
+                    let _ = tx.send(format!("Script {} finished: {}", script.display(), output.status));
+                }
+                Err(e) => {
+                    let _ = tx.send(format!("ERROR: Could not run {}: {}", script.display(), e));
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn poll_display_script(&mut self) {
+        let Some(rx) = &self.script_rx else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(line) => self.decode_logs.push(line),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.script_rx = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    // Warning, this function is LLM-generated. Mostly.
+    // Well, the AI code did not compile and used deprecated APIs. I fixed those. But the logic is from the LLM...
+    fn adjust_line_spaces_to_pdf(&mut self, ctx: &egui::Context) {
+        let text_id: egui::Id = egui::Id::new("shared_pdf_editor_id");
+        
+        if let Some(mut state) = egui::text_edit::TextEditState::load(ctx, text_id) {
+            if let Some(range) = state.cursor.char_range() {
+                let cursor_idx = range.primary.index;
+                let chars: Vec<char> = self.text_content.chars().collect();
+
+                // 1. Identify Key Indices in the Text Editor
+                // Find start of current line (scan back)
+                let mut line_start_idx = cursor_idx;
+                while line_start_idx > 0 && chars[line_start_idx - 1] != '\n' {
+                    line_start_idx -= 1;
+                }
+
+                // Find end of current line (scan forward to \n)
+                let mut newline_idx = None;
+                for i in cursor_idx..chars.len() {
+                    if chars[i] == '\n' {
+                        newline_idx = Some(i);
+                        break;
+                    }
+                }
+
+                if let Some(nl_idx) = newline_idx {
+                    // Find Last Non-Space Char of Current Line (A)
+                    let mut idx_a = nl_idx;
+                    while idx_a > line_start_idx && chars[idx_a - 1].is_whitespace() {
+                        idx_a -= 1;
+                    }
+                    // idx_a points to the first space *after* the text, or the newline if no spaces.
+                    // So idx_a - 1 is the actual character.
+                    if idx_a == line_start_idx { return; } // Empty line, nothing to align against
+                    let last_char_idx = idx_a - 1;
+
+                    // Find First Char of Next Line (B)
+                    // It is the character immediately after the newline (nl_idx + 1)
+                    let next_line_start_idx = nl_idx + 1;
+                    if next_line_start_idx >= chars.len() { return; } // EOF
+
+                    // 2. Consult PDF Logic
+                    if let Some(page) = Self::document_page(&self.documents, self.current_page_index) {
+                        if let Ok(text_page) = page.text() {
+                            
+                            // Get the PDF Rect for Item A (Last char of current line)
+                            // We use an iterator to be safe
+                            let chars = text_page.chars();
+                            let obj_a_opt = chars.iter().nth(last_char_idx);
+                            
+                            if let Some(obj_a) = obj_a_opt {
+                                if let Ok(rect_a) = obj_a.loose_bounds() {
+                                    
+                                    // 3. Scan PDF for the "True" Start of Next Line
+                                    // We look for the first character *after* A that satisfies the visual requirements:
+                                    // - Below A
+                                    // - Leftmost (reset X)
+                                    let mut found_pdf_target_idx = None;
+                                    
+                                    // Scan forward in PDF from A
+                                    for (offset, char_obj) in text_page.chars().iter().skip(last_char_idx + 1).enumerate() {
+                                        if let Ok(rect_curr) = char_obj.loose_bounds() {
+                                            let is_below = rect_curr.top().value < (rect_a.bottom().value + 2.0); // Tolerance
+                                            let is_leftmost = rect_curr.left().value < rect_a.left().value;
+                                            
+                                            if is_below && is_leftmost {
+                                                // Found it! 
+                                                // The index in PDF is last_char_idx + 1 + offset
+                                                found_pdf_target_idx = Some(last_char_idx + 1 + offset);
+                                                break;
+                                            }
+                                        }
+                                    }
+
+                                    // 4. Calculate Adjustment
+                                    if let Some(target_idx) = found_pdf_target_idx {
+                                        // The text editor currently thinks the next line starts at `next_line_start_idx`.
+                                        // The PDF says the next visual line starts at `target_idx`.
+                                        // The difference must be absorbed by spaces at the end of the current line.
+                                        
+                                        let current_diff = (next_line_start_idx as i32) - (last_char_idx as i32);
+                                        let target_diff = (target_idx as i32) - (last_char_idx as i32);
+                                        
+                                        // How many spaces do we need to add/remove?
+                                        // current_diff includes the spaces + the newline (1)
+                                        // target_diff includes the spaces + the newline (1) (logically)
+                                        let needed_change = target_diff - current_diff;
+
+                                        let mut final_newline_idx = nl_idx;
+
+                                        if needed_change > 0 {
+                                            // We are short on indices. Add spaces.
+                                            let spaces = " ".repeat(needed_change as usize);
+                                            // Insert before newline
+                                            let byte_offset = self.text_content.char_indices().nth(nl_idx).unwrap().0;
+                                            self.text_content.insert_str(byte_offset, &spaces);
+                                            final_newline_idx = nl_idx + needed_change as usize;
+                                            println!("Adjusted: Added {} spaces.", needed_change);
+                                        } else if needed_change < 0 {
+                                            // We have too many indices (too many spaces). Remove them.
+                                            let remove_count = (-needed_change) as usize;
+                                            
+                                            // Ensure we only remove spaces, don't eat text.
+                                            // Available spaces = nl_idx - idx_a
+                                            let available_spaces = nl_idx - idx_a;
+                                            
+                                            let safe_remove = remove_count.min(available_spaces);
+                                            
+                                            if safe_remove > 0 {
+                                                let start_remove_idx = nl_idx - safe_remove;
+                                                let byte_start = self.text_content.char_indices().nth(start_remove_idx).unwrap().0;
+                                                let byte_end = self.text_content.char_indices().nth(nl_idx).unwrap().0;
+                                                
+                                                self.text_content.replace_range(byte_start..byte_end, "");
+                                                final_newline_idx = nl_idx - safe_remove;
+                                                println!("Adjusted: Removed {} spaces.", safe_remove);
+                                            }
+                                        }
+
+                                        let new_cursor_pos = final_newline_idx + 1;
+                                        
+                                        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(
+                                            egui::text::CCursor::new(new_cursor_pos)
+                                        )));
+                                        
+                                        state.store(ctx, text_id);
+                                    }
+                                }
                             }
                         }
-                        if ctx.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.ctrl) {
-                            self.adjust_line_spaces_to_pdf(ctx);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// What the page thread is asked to do with a page
+enum PageJob {
+    Render,    // The page for the PDF view, render_size on its longest side
+    Thumbnail, // For the strip, THUMBNAIL_SIZE
+    Validity(Base64Alphabet, PathBuf), // Minimap stats of the page file, or of the PDF text without one
+}
+
+// A page for the page thread, with everything it needs to handle it on its own
+struct PageRequest {
+    index: u16,
+    generation: u64, // render_generation for Render, overview_generation otherwise
+    job: PageJob,
+    paths: Vec<String>,
+    password: Option<String>,
+    render_size: i32,
+    settings: Settings,
+}
+
+enum PageResult {
+    Rendered { index: u16, generation: u64, image: egui::ColorImage },
+    RenderFailed { index: u16, generation: u64, error: String }, // The page couldn't be opened or rendered
+    Thumbnail { index: u16, generation: u64, image: egui::ColorImage },
+    Validity { index: u16, generation: u64, validity: PageValidity },
+}
+
+// Renders the pages and reads them for the minimap, so neither a complex page nor a document
+// of thousands of pages freezes the window. The page the user is looking at always goes first,
+// the thumbnails and the minimap stats are worked through in between.
+// A PdfDocument can't leave the thread that opened it, so the thread opens the files again
+// and keeps them until it is asked for pages of other files.
+// Calls into the library are still serialized, so text and highlights wait for a render in progress.
+fn spawn_page_thread(
+    pdfium: &'static Pdfium,
+    ctx: egui::Context,
+) -> (mpsc::Sender<PageRequest>, mpsc::Receiver<PageResult>) {
+    let (request_tx, request_rx) = mpsc::channel::<PageRequest>();
+    let (result_tx, result_rx) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut opened: (Vec<String>, Option<String>) = (Vec::new(), None);
+        let mut documents: Vec<PdfDocument<'static>> = Vec::new();
+        let mut render: Option<PageRequest> = None;
+        let mut thumbnails: VecDeque<PageRequest> = VecDeque::new();
+        let mut validity: VecDeque<PageRequest> = VecDeque::new();
+
+        loop {
+            // Only wait when there is nothing left to do
+            let idle = render.is_none() && thumbnails.is_empty() && validity.is_empty();
+            let first = if idle {
+                match request_rx.recv() {
+                    Ok(request) => Some(request),
+                    Err(_) => break, // The app is gone
+                }
+            } else {
+                None
+            };
+            for request in first.into_iter().chain(request_rx.try_iter()) {
+                // Thumbnails and stats asked for before the documents or the overview changed
+                // are thrown away by the UI, don't work through them
+                let overview = !matches!(request.job, PageJob::Render);
+                let current = |queued: &PageRequest| {
+                    queued.paths == request.paths && (!overview || queued.generation >= request.generation)
+                };
+                thumbnails.retain(current);
+                validity.retain(current);
+                match request.job {
+                    // The user clicked on while we were busy, only the last page is still wanted
+                    PageJob::Render => render = Some(request),
+                    PageJob::Thumbnail => thumbnails.push_back(request),
+                    PageJob::Validity(..) => validity.push_back(request),
+                }
+            }
+
+            let Some(request) = render
+                .take()
+                .or_else(|| thumbnails.pop_front())
+                .or_else(|| validity.pop_front())
+            else {
+                continue;
+            };
+
+            if opened != (request.paths.clone(), request.password.clone()) {
+                documents = request
+                    .paths
+                    .iter()
+                    .map(|path| pdfium.load_pdf_from_file(path, request.password.as_deref()))
+                    .collect::<Result<_, _>>()
+                    .unwrap_or_else(|e| {
+                        eprintln!("Page thread could not open {}: {:?}", request.paths.join(", "), e);
+                        Vec::new()
+                    });
+                opened = (request.paths.clone(), request.password.clone());
+            }
+
+            let (index, generation) = (request.index, request.generation);
+            let page = PdfApp::document_page(&documents, index);
+            let result = match request.job {
+                // A failure must not take the thread down, the UI would wait for the page forever
+                PageJob::Render => match page.map(|page| PdfApp::render_page(&page, request.render_size, &request.settings)) {
+                    Some(Ok(image)) => PageResult::Rendered { index, generation, image },
+                    Some(Err(e)) => PageResult::RenderFailed { index, generation, error: format!("{:?}", e) },
+                    None => PageResult::RenderFailed {
+                        index,
+                        generation,
+                        error: "the document could not be opened".to_string(),
+                    },
+                },
+                PageJob::Thumbnail => {
+                    let Some(page) = page else {
+                        continue;
+                    };
+                    match PdfApp::render_page(&page, THUMBNAIL_SIZE, &request.settings) {
+                        Ok(image) => PageResult::Thumbnail { index, generation, image },
+                        Err(e) => {
+                            // Requested once, the overview shows the page without a thumbnail
+                            eprintln!("Could not render the thumbnail of page {}: {:?}", index + 1, e);
+                            continue;
+                        }
+                    }
+                }
+                PageJob::Validity(alphabet, path) => {
+                    // Like load_page: the saved file if there is one, the PDF text otherwise
+                    let text = fs::read_to_string(path)
+                        .ok()
+                        .or_else(|| page.and_then(|page| page.text().ok()).map(|text| text.all()))
+                        .unwrap_or_default();
+                    let text = PdfApp::clean_carriage_returns(&text, request.settings.keep_carriage_returns);
+                    let validity = PageValidity::of(&text, alphabet, request.settings.line_length);
+                    PageResult::Validity { index, generation, validity }
+                }
+            };
+            if result_tx.send(result).is_err() {
+                break; // The app is gone
+            }
+            ctx.request_repaint();
+        }
+    });
+
+    (request_tx, result_rx)
+}
+
+// Sends the results of a decode running on a worker thread back to the UI
+#[derive(Clone)]
+struct StreamDecoder {
+    tx: mpsc::Sender<DecodeMessage>,
+    ctx: Option<egui::Context>, // To wake up the UI when there is something new, None when headless
+    alphabet: Base64Alphabet,
+    filter: CleaningFilter,
+    per_page: bool, // Decode every page file on its own instead of one concatenated stream
+    recursive: bool, // Include page files in subfolders of the workdir
+    decompress: bool, // Inflate gzip/zlib bytes before scanning them, the raw bytes if that fails
+    try_alignments: bool, // Scan the alignment of the 4-char groups that decodes the most images
+    partial_jpeg: bool, // Lenient fallback for JPEGs the strict decode fails on
+    page_files: PageFilePattern,
+}
+
+impl StreamDecoder {
+    fn send(&self, msg: DecodeMessage) {
+        // The UI may have started another decode and dropped the receiver, that's fine
+        let _ = self.tx.send(msg);
+        if let Some(ctx) = &self.ctx {
+            ctx.request_repaint();
+        }
+    }
+
+    fn log(&self, msg: String) {
+        self.send(DecodeMessage::Log(msg));
+    }
+
+    fn run(&self, workdir: &Path) {
+        let (file_names, file_contents) = self.load_page_files(workdir);
+
+        if self.per_page {
+            // Every file is a stream of its own, so a broken tail doesn't bleed into the next page
+            let mut first_misaligned = None;
+            for (i, (name, content)) in file_names.iter().zip(&file_contents).enumerate() {
+                self.log(format!("--- {} ---", name));
+                if first_misaligned.is_none()
+                    && decode::clean_page(content, self.alphabet, &self.filter).len() % self.alphabet.group_chars() != 0
+                {
+                    first_misaligned = Some(name.clone());
+                }
+                self.send(DecodeMessage::Source(name.clone()));
+                self.decode_and_scan(std::slice::from_ref(name), std::slice::from_ref(content));
+                self.send(DecodeMessage::Progress(0.5 + 0.5 * (i + 1) as f32 / file_names.len() as f32));
+            }
+            if let Some(name) = first_misaligned {
+                self.log(format!(
+                    "WARNING: {} is the first page whose length is not a multiple of {}",
+                    name,
+                    self.alphabet.group_chars()
+                ));
+            }
+        } else {
+            self.send(DecodeMessage::Progress(0.5));
+            self.decode_and_scan(&file_names, &file_contents);
+        }
+        self.send(DecodeMessage::Progress(1.0));
+    }
+
+    // Decodes the page files and checks that the bytes re-encode to the same text
+    fn run_round_trip(&self, workdir: &Path) {
+        let (file_names, file_contents) = self.load_page_files(workdir);
+        self.log("Verifying the round trip...".into());
+
+        let mut logs = Vec::new();
+        decode::verify_round_trip(&file_names, &file_contents, self.alphabet, &self.filter, &mut logs);
+        for msg in logs {
+            self.log(msg);
+        }
+        self.send(DecodeMessage::Progress(1.0));
+    }
+
+    // The page files of the workdir in page order, as (names, contents)
+    fn load_page_files(&self, workdir: &Path) -> (Vec<String>, Vec<String>) {
+        // 1. Load and Sort Files
+        self.log(format!(
+            "Scanning {}{} for {}...",
+            workdir.display(),
+            if self.recursive { " and its subfolders" } else { "" },
+            self.page_files.describe()
+        ));
+        let mut file_names = Vec::new();
+        let mut file_contents = Vec::new();
+        
+        let files = list_page_files(workdir, self.recursive, &self.page_files);
+        for (i, file) in files.iter().enumerate() {
+            if let Ok(content) = fs::read_to_string(file) {
+                // Relative to the workdir, e.g. "doc2/page001.txt" when scanning subfolders
+                let name = file.strip_prefix(workdir).unwrap_or(file).to_string_lossy().to_string();
+                if self.recursive {
+                    self.log(format!("Loaded: {}", name));
+                }
+                file_names.push(name);
+                file_contents.push(content);
+            }
+            // Loading is the first half of the bar, scanning the second
+            self.send(DecodeMessage::Progress(0.5 * (i + 1) as f32 / files.len() as f32));
+        }
+        self.log(format!("Loaded {} files", file_contents.len()));
+        (file_names, file_contents)
+    }
+
+    fn run_text(&self, name: &str, text: &str) {
+        self.log(format!("Decoding the {}...", name));
+        // Trim cuts the header and footer of a page file, a pasted or selected block has neither
+        let decoder = StreamDecoder {
+            filter: self.filter.without_trim(),
+            ..self.clone()
+        };
+        decoder.decode_and_scan(&[name.to_string()], &[text.to_string()]);
+        self.send(DecodeMessage::Progress(1.0));
+    }
+
+    // The last file is the partial page, its name says where the prefix ends
+    fn run_prefix(&self, file_names: &[String], file_contents: &[String]) {
+        let end = file_names.last().cloned().unwrap_or_default();
+        self.log(format!("Decoding {} file(s), up to {}...", file_names.len(), end));
+        self.send(DecodeMessage::Progress(0.5));
+
+        // Trimmed here: trim_end only applies to the complete page files, not to the last one
+        // that stops at the cursor (it would cut the lines just above it)
+        let last = file_contents.len().saturating_sub(1);
+        let trimmed: Vec<String> = file_contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| self.filter.blank_trimmed(content, i == last))
+            .collect();
+        let decoder = StreamDecoder {
+            filter: self.filter.without_trim(),
+            ..self.clone()
+        };
+        let bytes = decoder.decode_base64_stream(file_names, &trimmed);
+        self.send(DecodeMessage::DecodedBytes(bytes.clone()));
+        if !bytes.is_empty() {
+            self.recover_jpegs_from_stream(&bytes);
+        }
+
+        let candidates = decode::find_image_candidates(&bytes, &mut Vec::new());
+        let verdict = match candidates.last() {
+            Some(last) if last.format.is_some() && last.truncated => format!(
+                "RESULT: the last image (offset 0x{:X}) is still truncated at {}",
+                last.offset, end
+            ),
+            Some(last) if last.format.is_some() => format!(
+                "RESULT: the last image (offset 0x{:X}) is complete at {}",
+                last.offset, end
+            ),
+            _ => format!("RESULT: no image starts before {}", end),
+        };
+        self.log(verdict);
+        self.send(DecodeMessage::Progress(1.0));
+    }
+
+    // Decodes the stream once for every value of the char at `position` of file `file`, then
+    // ranks them: most complete images first, then most JPEG rows. The best one is scanned
+    // like a regular decode, to see it.
+    fn run_char_search(
+        &self,
+        file_names: &[String],
+        mut file_contents: Vec<String>,
+        file: usize,
+        page: u16,
+        position: usize,
+    ) {
+        let original = file_contents[file].clone();
+        let Some((byte_idx, current)) = original.char_indices().nth(position) else {
+            return;
+        };
+        let with_value = |value: char| {
+            let mut text = original.clone();
+            text.replace_range(byte_idx..byte_idx + current.len_utf8(), value.encode_utf8(&mut [0; 4]));
+            text
+        };
+
+        let values = decode::possible_chars(self.alphabet);
+        self.log(format!(
+            "Trying the {} values of the char '{}' of {} across {} file(s)...",
+            values.len(),
+            current,
+            file_names[file],
+            file_names.len()
+        ));
+        let mut ranked = Vec::new();
+        for (i, &value) in values.iter().enumerate() {
+            file_contents[file] = with_value(value);
+            // Logs would repeat for every value
+            let bytes = decode::decode_base64_stream(file_names, &file_contents, self.alphabet, &self.filter, &mut Vec::new());
+            let bytes = self.decompressed(bytes, &mut Vec::new());
+            let (complete_images, jpeg_rows) = decode::decode_score(&bytes);
+            ranked.push(CharCandidate {
+                value,
+                complete_images,
+                jpeg_rows,
+            });
+            self.send(DecodeMessage::Progress(0.9 * (i + 1) as f32 / values.len() as f32));
+        }
+        // Stable, so a tie stays in alphabet order
+        ranked.sort_by_key(|candidate| std::cmp::Reverse((candidate.complete_images, candidate.jpeg_rows)));
+
+        for candidate in ranked.iter().take(5) {
+            self.log(format!(
+                "'{}': {} complete image(s), {} JPEG row(s)",
+                candidate.value, candidate.complete_images, candidate.jpeg_rows
+            ));
+        }
+        let best = ranked[0].value;
+        let score = |candidate: &CharCandidate| (candidate.complete_images, candidate.jpeg_rows);
+        if ranked.iter().all(|candidate| score(candidate) == score(&ranked[0])) {
+            self.log("RESULT: every value decodes the same, the char is probably not inside an image".into());
+        } else {
+            self.log(format!("RESULT: '{}' decodes the furthest, scanning the stream with it", best));
+        }
+
+        file_contents[file] = with_value(best);
+        self.send(DecodeMessage::CharSearch(CharSearch { page, position, ranked }));
+        let bytes = self.decode_base64_stream(file_names, &file_contents);
+        self.send(DecodeMessage::DecodedBytes(bytes.clone()));
+        if !bytes.is_empty() {
+            self.recover_jpegs_from_stream(&bytes);
+        }
+        self.send(DecodeMessage::Progress(1.0));
+    }
+
+    fn decode_and_scan(&self, file_names: &[String], file_contents: &[String]) {
+        let bytes = if self.try_alignments {
+            self.decode_best_alignment(file_names, file_contents)
+        } else {
+            self.decode_base64_stream(file_names, file_contents)
+        };
+        self.send(DecodeMessage::DecodedBytes(bytes.clone()));
+        if !bytes.is_empty() {
+            self.recover_jpegs_from_stream(&bytes);
+        }
+    }
+
+    fn decode_base64_stream(&self, file_names: &[String], file_contents: &[String]) -> Vec<u8> {
+        let mut logs = Vec::new();
+        let bytes = decode::decode_base64_stream(
+            file_names,
+            file_contents,
+            self.alphabet,
+            &self.filter,
+            &mut logs,
+        );
+        let bytes = self.decompressed(bytes, &mut logs);
+        for msg in logs {
+            self.log(msg);
+        }
+        bytes
+    }
+
+    fn decompressed(&self, bytes: Vec<u8>, logs: &mut Vec<String>) -> Vec<u8> {
+        if self.decompress && !bytes.is_empty() {
+            decode::decompress(&bytes, logs).unwrap_or(bytes)
+        } else {
+            bytes
+        }
+    }
+
+    // A char lost early on shifts every 4-char group after it, so the stream is also decoded
+    // with 1 to 3 leading chars skipped (1 for hex). The alignment with the most images wins,
+    // the regular one on a tie.
+    fn decode_best_alignment(&self, file_names: &[String], file_contents: &[String]) -> Vec<u8> {
+        let mut best_bytes = self.decode_base64_stream(file_names, file_contents);
+        let mut best_count = decode::count_decodable_images(&best_bytes);
+        let mut best_skip = 0;
+        self.log(format!("Alignment +0: {} image(s) decode", best_count));
+
+        for skip in 1..self.alphabet.group_chars() {
+            // Decompression logs would repeat for every alignment
+            let bytes = decode::decode_stream_skipping(file_contents, self.alphabet, &self.filter, skip);
+            let bytes = self.decompressed(bytes, &mut Vec::new());
+            let count = decode::count_decodable_images(&bytes);
+            self.log(format!("Alignment +{}: {} image(s) decode", skip, count));
+            if count > best_count {
+                (best_bytes, best_count, best_skip) = (bytes, count, skip);
+            }
+        }
+
+        if best_count == 0 {
+            self.log("RESULT: no alignment decodes a complete image, scanning the regular one".into());
+        } else {
+            self.log(format!(
+                "RESULT: alignment +{} (skipping {} leading char(s)) decodes the most images, scanning that one",
+                best_skip, best_skip
+            ));
+        }
+        best_bytes
+    }
+
+    fn recover_jpegs_from_stream(&self, bytes: &[u8]) {
+        let mut logs = Vec::new();
+        let candidates = decode::find_image_candidates(bytes, &mut logs);
+        for msg in logs {
+            self.log(msg);
+        }
+
+        // Only the pixels are left to us, the UI thread turns them into textures
+        for candidate in candidates {
+            if let (true, Some(format)) = (candidate.truncated, candidate.format) {
+                self.send(DecodeMessage::Truncated);
+                self.log(format!(
+                    "-> {:?} segment at offset 0x{:X} is truncated (no end marker)",
+                    format, candidate.offset
+                ));
+            }
+            self.decode_image_segment(candidate.bytes, candidate.format, candidate.offset, candidate.truncated);
+        }
+    }
+
+    fn decode_image_segment(
+        &self,
+        bytes: &[u8],
+        format: Option<image::ImageFormat>,
+        offset: usize, // Where the segment starts in the decoded stream
+        truncated: bool,
+    ) {
+        let is_jpeg = format.or_else(|| image::guess_format(bytes).ok()) == Some(image::ImageFormat::Jpeg);
+
+        // The strict decode pads a cut off JPEG with garbage, the partial one tells how much is real
+        if truncated && is_jpeg && self.partial_jpeg && self.recover_partial_jpeg(bytes, offset, true) {
+            return;
+        }
+
+        // Attempt to decode, letting the image crate guess if we don't know the format
+        let result = match format {
+            Some(format) => image::load_from_memory_with_format(bytes, format),
+            None => image::load_from_memory(bytes),
+        };
+        let format_name = match format {
+            Some(format) => format!("{:?}", format),
+            None => image::guess_format(bytes)
+                .map(|f| format!("{:?}", f))
+                .unwrap_or_else(|_| "unknown format".to_string()),
+        };
+
+        match result {
+            Ok(img) => {
+                let size = [img.width() as usize, img.height() as usize];
+                let color_image = egui::ColorImage::from_rgb(size, &img.to_rgb8());
+
+                // The texture is uploaded on the UI thread
+                self.send(DecodeMessage::Image {
+                    color_image,
+                    bytes: bytes.to_vec(),
+                    format: format.or_else(|| image::guess_format(bytes).ok()),
+                    offset,
+                    partial: None,
+                });
+                self.log(format!(
+                    "-> SUCCESS: Recovered {} image {}x{} from {} bytes at offset 0x{:X}",
+                    format_name,
+                    img.width(),
+                    img.height(),
+                    bytes.len(),
+                    offset
+                ));
+
+            },
+            Err(e) => {
+                self.log(format!(
+                    "-> FAILED to decode {} image at offset 0x{:X}: {}",
+                    format_name, offset, e
+                ));
+                if self.partial_jpeg && is_jpeg {
+                    self.recover_partial_jpeg(bytes, offset, false);
+                }
+            }
+        
+        }
+    }
+
+    // Most of a JPEG with a corrupt or missing tail is usually fine, show how much of it survived.
+    // False if not a single row could be decoded.
+    fn recover_partial_jpeg(&self, bytes: &[u8], offset: usize, truncated: bool) -> bool {
+        // Lenient mode would fill the missing tail of a truncated JPEG, strict stops where the data does
+        let partial = match decode::decode_partial_jpeg(bytes, truncated) {
+            Some(partial) if partial.decoded_rows > 0 => partial,
+            _ => {
+                self.log("-> Partial recovery found no readable rows".into());
+                return false;
+            }
+        };
+
+        let color_image = egui::ColorImage::from_rgb([partial.width, partial.height], &partial.rgb);
+        self.send(DecodeMessage::Image {
+            color_image,
+            bytes: bytes.to_vec(),
+            format: Some(image::ImageFormat::Jpeg),
+            offset,
+            partial: Some(PartialImage {
+                truncated,
+                decoded_fraction: partial.decoded_rows as f32 / partial.height as f32,
+            }),
+        });
+        self.log(format!(
+            "-> PARTIAL: Recovered {} of {} rows ({:.0}%) of the {}x{} JPEG at offset 0x{:X}{}",
+            partial.decoded_rows,
+            partial.height,
+            100.0 * partial.decoded_rows as f32 / partial.height as f32,
+            partial.width,
+            partial.height,
+            offset,
+            match &partial.error {
+                Some(e) => format!(", stopped at: {}", e),
+                None => String::new(),
+            }
+        ));
+        true
+    }
+}
+
+impl eframe::App for PdfApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if !self.document_paths.is_empty() {
+            // One path per line
+            storage.set_string(LAST_FILE_KEY, self.document_paths.join("\n"));
+            storage.set_string(LAST_PAGE_KEY, self.current_page_index.to_string());
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.autosave_if_needed(ctx);
+        self.poll_stream_decoding(ctx);
+        self.poll_display_script();
+        self.poll_page_thread(ctx);
+
+        // Before the central panel, which takes whatever space is left
+        self.show_validity_minimap(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let text_id = egui::Id::new("shared_pdf_editor_id");
+            ui.horizontal(|ui| {
+                if ui.button("Open…").on_hover_text("Pick one or more PDFs, their pages form one range").clicked() {
+                    self.open_document_dialog();
+                }
+                if ui.button("Add…").on_hover_text("Append more PDFs after the last page").clicked() {
+                    self.add_document_dialog();
+                }
+
+                ui.separator();
+
+                if ui.button("Prev").clicked() && self.current_page_index > 0 {
+                    self.navigate_to_page(self.current_page_index - 1);
+                }
+                let page_label = ui.label(format!(
+                    "Page {} / {}",
+                    self.current_page_index + 1,
+                    self.total_pages
+                ));
+                if let Some((doc, local)) = Self::locate_page(&self.documents, self.current_page_index) {
+                    page_label.on_hover_text(format!("{}, page {}", self.document_paths[doc], local + 1));
+                }
+                if ui.button("Next").clicked() && self.current_page_index + 1 < self.total_pages {
+                    self.navigate_to_page(self.current_page_index + 1);
+                }
+
+                let bookmarked = self.bookmarks.contains(&self.current_page_index);
+                if ui
+                    .selectable_label(bookmarked, "🔖")
+                    .on_hover_text(if bookmarked { "Remove the bookmark (Ctrl+B)" } else { "Bookmark this page for later review (Ctrl+B)" })
+                    .clicked()
+                {
+                    self.toggle_bookmark();
+                }
+                if !self.bookmarks.is_empty() {
+                    if ui.small_button("◀").on_hover_text("Previous bookmarked page (Shift+F2)").clicked() {
+                        self.jump_to_bookmark(false);
+                    }
+                    if ui.small_button("▶").on_hover_text("Next bookmarked page (F2)").clicked() {
+                        self.jump_to_bookmark(true);
+                    }
+                }
+
+                let goto_id = egui::Id::new("goto_page_input");
+                let goto_response = ui.add(
+                    egui::TextEdit::singleline(&mut self.goto_page_input)
+                        .id(goto_id)
+                        .hint_text("Go to")
+                        .desired_width(50.0),
+                );
+                if goto_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.perform_goto_page();
+                }
+                if !self.goto_page_msg.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.goto_page_msg);
+                }
+
+                ui.separator();
+
+                let ambiguous_label: Vec<String> =
+                    self.settings.ambiguous_chars.iter().map(|c| c.to_string()).collect();
+                if ui.button(format!("Jump to {}", ambiguous_label.join("/"))).clicked() {
+                    self.jump_to_ilone(ctx);
+                }
+
+                if ui
+                    .toggle_value(&mut self.side_by_side, "Side by Side")
+                    .on_hover_text("Page next to the editor, following its scrolling")
+                    .changed()
+                {
+                    self.synced_top_line = None;
+                }
+
+                ui.toggle_value(&mut self.show_problem_lines, "Problem Lines")
+                    .on_hover_text("List the lines that are not a full line of valid Base64 characters");
+
+                ui.toggle_value(&mut self.show_pdf_text, "PDF Text")
+                    .on_hover_text("Compare the text extracted from the PDF with the editor");
+
+                if ui
+                    .toggle_value(&mut self.show_page_diff, "Compare Page")
+                    .on_hover_text("Diff the editor with another page, to spot where one of them broke")
+                    .changed()
+                    && self.show_page_diff
+                {
+                    // The previous page by default, they usually share the structure
+                    self.compare_page_index = self.current_page_index.saturating_sub(1);
+                    self.load_compare_page();
+                }
+
+                let placeholders_label = match self.placeholders.len() {
+                    0 => "Placeholders".to_string(),
+                    count => format!("Placeholders ({})", count),
+                };
+                ui.toggle_value(&mut self.show_placeholders, placeholders_label)
+                    .on_hover_text("Characters inserted with Ctrl+I where OCR dropped one");
+
+                ui.toggle_value(&mut self.show_histogram, "Histogram")
+                    .on_hover_text("How often every Base64 character occurs, OCR substitutions stand out");
+
+                ui.toggle_value(&mut self.highlight_all_matches, "Highlight All")
+                    .on_hover_text("Underline every occurrence of the selected text on the page");
+
+                if ui.button("Hex Jump").clicked() {
+                    self.show_hex_dialog = true;
+                    self.hex_input.clear();
+                    self.jump_status_msg.clear();
+                }
+
+                ui.separator();
+
+                if ui.button("Save").clicked() {
+                    self.save_and_decode(ctx);
+                }
+
+                if ui
+                    .button(format!("Rewrap to {}", self.settings.line_length))
+                    .on_hover_text("Remove all whitespace and break the page into lines of equal length")
+                    .clicked()
+                {
+                    self.rewrap_page();
+                }
+
+                if ui.button("Reload").on_hover_text("Read the page file again, after editing it elsewhere (F5)").clicked() {
+                    self.request_reload_page_file();
+                }
+
+                if ui.button("Replace").clicked() {
+                    self.show_replace_dialog = true;
+                    self.replace_status_msg.clear();
+                }
+
+                if ui.button("Save All").clicked() {
+                    self.request_save_all_pages();
+                }
+
+                // Keyboard shortcuts
+                if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.ctrl && !i.modifiers.shift) {
+                    self.save_and_decode(ctx);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.ctrl && i.modifiers.shift) {
+                    self.request_save_all_pages();
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::J) && i.modifiers.ctrl && !i.modifiers.shift) {
+                    self.jump_to_ilone(ctx);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::J) && i.modifiers.ctrl && i.modifiers.shift) {
+                    self.jump_to_prev_ambiguous(ctx);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::L) && i.modifiers.ctrl && !i.modifiers.shift) {
+                    self.select_current_line(ctx);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::L) && i.modifiers.ctrl && i.modifiers.shift) {
+                    self.jump_to_next_wrong_length(ctx);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::B) && i.modifiers.ctrl) && !self.documents.is_empty() {
+                    self.toggle_bookmark();
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::F2) && !i.modifiers.shift) {
+                    self.jump_to_bookmark(true);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::F2) && i.modifiers.shift) {
+                    self.jump_to_bookmark(false);
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::P) && i.modifiers.ctrl) {
+                    ctx.memory_mut(|m| m.request_focus(goto_id));
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::F5)) {
+                    self.request_reload_page_file();
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::F) && i.modifiers.ctrl) {
+                    self.show_find_bar = true;
+                    self.focus_find_bar = true;
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::H) && i.modifiers.ctrl) {
+                    self.show_replace_dialog = true;
+                    self.replace_status_msg.clear();
+                }
+                if ctx.input(|i| i.key_pressed(egui::Key::G) && i.modifiers.ctrl) {
+                    self.show_hex_dialog = true;
+                    self.hex_input.clear();
+                    self.jump_status_msg.clear();
+                }
+                // Keyboard shortcuts
+                if ctx.input(|i| i.key_pressed(egui::Key::D) && i.modifiers.ctrl) {
+                    self.display_script(ctx);
+                }
+                // Page navigation. Only when no text field has focus, there these keys move the cursor
+                if ctx.memory(|m| m.focused().is_none()) && self.total_pages > 0 {
+                    let last_page = self.total_pages - 1;
+                    let target = ctx.input(|i| {
+                        if i.key_pressed(egui::Key::PageDown) || i.key_pressed(egui::Key::ArrowRight) {
+                            Some((self.current_page_index + 1).min(last_page))
+                        } else if i.key_pressed(egui::Key::PageUp) || i.key_pressed(egui::Key::ArrowLeft) {
+                            Some(self.current_page_index.saturating_sub(1))
+                        } else if i.key_pressed(egui::Key::Home) {
+                            Some(0)
+                        } else if i.key_pressed(egui::Key::End) {
+                            Some(last_page)
+                        } else {
+                            None
                         }
+                    });
+                    if let Some(index) = target.filter(|&index| index != self.current_page_index) {
+                        self.navigate_to_page(index);
+                    }
+                }
 
-                        let text_edit = egui::TextEdit::multiline(&mut self.text_content)
-                            .id(text_id)
-                            .desired_width(f32::INFINITY)
-                            .horizontal_align(emath::Align::Center)
-                            .font(egui::FontId::new(font_size, egui::FontFamily::Monospace));
+                if ui.button("Display").clicked() {
+                    self.display_script(ctx);
+                }
 
-                        let text_edit_response = text_edit.show(ui);
-                        // This is an attempt to keep cursor visible in the text editor
-                        if text_edit_response.response.has_focus() {
-                            if let Some(cursor_range) = text_edit_response.cursor_range {
-                                let cursor_relative_rect = text_edit_response.galley.pos_from_cursor(cursor_range.primary);
-                                
-                                let cursor_screen_rect = cursor_relative_rect.translate(text_edit_response.galley_pos.to_vec2());
-                                
-                                ui.scroll_to_rect(cursor_screen_rect, None);
-                            }                        
+                ui.separator();
+
+                let theme_icon = if self.settings.dark_mode { "☀" } else { "🌙" };
+                if ui.button(theme_icon).on_hover_text("Switch between the dark and light theme").clicked() {
+                    self.settings.dark_mode = !self.settings.dark_mode;
+                    self.settings.save();
+                    self.apply_theme(ctx);
+                }
+
+                if ui.button("Settings").clicked() {
+                    self.show_settings = !self.show_settings;
+                    self.ambiguous_chars_input = self.settings.ambiguous_chars.iter().collect();
+                }
+                if ui.button("?").on_hover_text("Keyboard shortcuts (F1)").clicked()
+                    || ctx.input(|i| i.key_pressed(egui::Key::F1))
+                {
+                    self.show_shortcuts = !self.show_shortcuts;
+                }
+
+                if let Some(offset) = self.get_cursor_binary_offset(ctx) {
+                    ui.separator();
+                    ui.monospace(format!("Byte 0x{:X}", offset))
+                        .on_hover_text("Binary offset of the cursor in the decoded stream");
+                }
+
+                if !self.documents.is_empty() {
+                    ui.separator();
+                    let source = if self.text_from_file { "source: saved file" } else { "source: PDF extraction" };
+                    let hover = if self.text_from_file {
+                        format!("The editor shows {}, your corrections", self.page_file_path(self.current_page_index).display())
+                    } else {
+                        "The editor shows the text extracted from the PDF, no page file was saved yet".to_string()
+                    };
+                    ui.label(if self.text_dirty { format!("{} (edited)", source) } else { source.to_string() })
+                        .on_hover_text(hover);
+                }
+
+                if !self.status_msg.is_empty() {
+                    ui.separator();
+                    ui.label(&self.status_msg);
+                }
+
+                // Show the autosave indicator for a few seconds after it fired
+                if let Some(saved_at) = self.last_autosave {
+                    if saved_at.elapsed() < Duration::from_secs(3) {
+                        ui.separator();
+                        ui.colored_label(egui::Color32::GREEN, "saved");
+                    }
+                }
+            });
+
+            // --- THUMBNAIL STRIP ---
+            egui::ScrollArea::horizontal()
+                .id_salt("thumbnail_scroll")
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let thumbnail_size = egui::vec2(THUMBNAIL_SIZE as f32, THUMBNAIL_SIZE as f32);
+                        for index in 0..self.total_pages {
+                            let (rect, response) = ui.allocate_exact_size(thumbnail_size, egui::Sense::click());
+                            // Only the visible ones are rendered
+                            if !ui.is_rect_visible(rect) {
+                                continue;
+                            }
+
+                            let texture = self.thumbnail_texture(index);
+
+                            let painter = ui.painter();
+                            if let Some(texture) = texture {
+                                painter.image(
+                                    texture.id(),
+                                    egui::Rect::from_center_size(rect.center(), texture.size_vec2()),
+                                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                                    egui::Color32::WHITE,
+                                );
+                            }
+                            if index == self.current_page_index {
+                                painter.rect_stroke(
+                                    rect,
+                                    0.0,
+                                    egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE),
+                                    egui::StrokeKind::Inside,
+                                );
+                            }
+                            if self.bookmarks.contains(&index) {
+                                painter.text(
+                                    rect.left_top() + egui::vec2(4.0, 4.0),
+                                    egui::Align2::LEFT_TOP,
+                                    "🔖",
+                                    egui::FontId::proportional(20.0),
+                                    ui.visuals().warn_fg_color,
+                                );
+                            }
+                            // Pages already corrected and saved
+                            if self.page_file_path(index).exists() {
+                                painter.text(
+                                    rect.right_top() + egui::vec2(-4.0, 4.0),
+                                    egui::Align2::RIGHT_TOP,
+                                    "✔",
+                                    egui::FontId::proportional(20.0),
+                                    egui::Color32::GREEN,
+                                );
+                            }
+
+                            if response.on_hover_text(format!("Page {}", index + 1)).clicked() {
+                                self.navigate_to_page(index);
+                            }
                         }
                     });
                 });
 
+            // Space below the toolbar is shared by the PDF view, the editor and the results,
+            // using the fractions the user dragged the splitters to
+            let available_height = ui.available_height();
+            let pdf_height = available_height * self.pdf_view_fraction;
+            let editor_height = available_height * self.editor_fraction;
+
+            if self.side_by_side {
+                // The page and the editor next to each other, taking the space of both
+                self.show_find_bar(ui, ctx);
+                let height = pdf_height + editor_height;
+                ui.columns(2, |columns| {
+                    self.show_pdf_view(&mut columns[0], ctx, text_id, height);
+                    self.show_editor(&mut columns[1], ctx, text_id, height);
+                });
+            } else {
+                // --- TOP SECTION: PDF VIEW ---
+                self.show_pdf_view(ui, ctx, text_id, pdf_height);
+
+                Self::splitter(ui, &mut self.pdf_view_fraction, available_height);
+
+                // --- FIND BAR ---
+                self.show_find_bar(ui, ctx);
+
+                // --- BOTTOM SECTION: SPLIT EDITOR ---
+                self.show_editor(ui, ctx, text_id, editor_height);
+            }
+
+            ui.horizontal(|ui| {
                 ui.label(format!(
                     "Line {} / {}",
                     self.get_current_line_number(&ctx),
                     self.text_content.lines().count()
                 ));
 
-                // let available_height = ui.available_height();
+                ui.separator();
+                if ui.small_button("A-").on_hover_text("Smaller editor font (or Ctrl+scroll)").clicked() {
+                    self.set_editor_font_size(self.settings.editor_font_size - 2.0);
+                }
+                ui.label(format!("{:.0}", self.settings.editor_font_size));
+                if ui.small_button("A+").on_hover_text("Bigger editor font (or Ctrl+scroll)").clicked() {
+                    self.set_editor_font_size(self.settings.editor_font_size + 2.0);
+                }
+
+                ui.separator();
+                if ui.button("Copy Base64").on_hover_text("Copy the cleaned Base64 of this page").clicked() {
+                    self.copy_page_base64(ctx);
+                }
+                if ui.button("Copy Hex").on_hover_text("Copy the bytes of this page decoded on its own, as hex").clicked() {
+                    self.copy_page_hex(ctx);
+                }
+                if ui.button("Decode to Cursor")
+                    .on_hover_text("Decode the previous pages and this page up to the end of the cursor line")
+                    .clicked()
+                {
+                    self.run_decode_to_cursor(ctx);
+                }
+                if ui.button("Decode Selection")
+                    .on_hover_text("Decode only the text selected in the editor")
+                    .clicked()
+                {
+                    self.run_selection_decoding(ctx);
+                }
+                if !self.pinned_selections.is_empty()
+                    && ui.button(format!("Clear Pinned ({})", self.pinned_selections.len()))
+                        .on_hover_text("Remove the Ctrl+click highlights")
+                        .clicked()
+                {
+                    self.pinned_selections.clear();
+                }
+            });
+
+            // Side by side the editor takes the height of the page view, there is nothing to split
+            if !self.side_by_side {
+                Self::splitter(ui, &mut self.editor_fraction, available_height);
+            }
 
-                // --- BOTTOM: DECODED IMAGES & LOGS ---
-                egui::ScrollArea::vertical()
-                    .id_salt("decode_scroll")
-                    .show(ui, |ui| {
+            // --- BOTTOM: DECODED IMAGES & LOGS ---
+            egui::ScrollArea::vertical()
+                .id_salt("decode_scroll")
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
                         ui.heading("Decoded Stream Results");
+                        if !self.recovered_images.is_empty() {
+                            let held = self.recovered_images.iter().filter(|r| r.texture.is_some()).count();
+                            ui.weak(format!(
+                                "{} textures, {:.1} MB",
+                                held,
+                                self.texture_memory_estimate() as f64 / (1024.0 * 1024.0)
+                            ))
+                            .on_hover_text(format!(
+                                "GPU memory of the recovered images, the oldest are freed above {} (Settings)",
+                                self.settings.max_image_textures
+                            ));
+                        }
+                        if ui.button("Save Images").clicked() {
+                            self.save_recovered_images();
+                        }
+                        if ui.button("Export Log")
+                            .on_hover_text("Append the logs and the decode settings to decode_log.txt")
+                            .clicked()
+                        {
+                            self.export_decode_log();
+                        }
+                        if ui.button("Export Combined")
+                            .on_hover_text(format!("Write all page files, in decode order, to {}", COMBINED_FILE_NAME))
+                            .clicked()
+                        {
+                            self.export_combined_text();
+                        }
+                        ui.checkbox(&mut self.combined_one_line, "One line")
+                            .on_hover_text("Strip the line breaks from the combined file, one continuous Base64 string");
+                        if ui.button("Verify Round Trip")
+                            .on_hover_text("Decode the page files, encode the bytes again and log where they differ from the text")
+                            .clicked()
+                        {
+                            self.run_round_trip_check(ctx);
+                        }
+
+                        ui.separator();
+                        // Which chars count and which files are read, the cursor offset depends on all of it
+                        let previous_cleaning = (self.alphabet, self.cleaning_filter.clone(), self.recursive_scan);
+                        ui.selectable_value(&mut self.alphabet, Base64Alphabet::Standard, "Standard (+/)");
+                        ui.selectable_value(&mut self.alphabet, Base64Alphabet::UrlSafe, "URL-safe (-_)");
+                        ui.selectable_value(&mut self.alphabet, Base64Alphabet::Hex, "Hex")
+                            .on_hover_text("The text is a hex dump, not Base64: pairs of hex digits, everything else ignored");
+
+                        // Applied on the next decode
+                        ui.separator();
+                        ui.label("Strip:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.cleaning_filter.strip_chars)
+                                .hint_text("chars")
+                                .desired_width(60.0),
+                        )
+                        .on_hover_text("Any of these chars is removed before decoding");
+
+                        ui.label("Trim:");
+                        ui.add(egui::DragValue::new(&mut self.cleaning_filter.trim_start).range(0..=100_000))
+                            .on_hover_text("Skipped at the start of every page file");
+                        ui.add(egui::DragValue::new(&mut self.cleaning_filter.trim_end).range(0..=100_000))
+                            .on_hover_text("Skipped at the end of every page file");
+                        egui::ComboBox::from_id_salt("trim_unit")
+                            .selected_text(match self.cleaning_filter.trim_unit {
+                                TrimUnit::Chars => "chars",
+                                TrimUnit::Lines => "lines",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.cleaning_filter.trim_unit, TrimUnit::Chars, "chars");
+                                ui.selectable_value(&mut self.cleaning_filter.trim_unit, TrimUnit::Lines, "lines");
+                            });
+
+                        ui.checkbox(&mut self.per_page_decode, "Per page")
+                            .on_hover_text("Decode every page file as a separate Base64 stream");
+                        ui.checkbox(&mut self.recursive_scan, "Subfolders")
+                            .on_hover_text("Also decode the page files in subfolders, folder by folder in name order");
+                        ui.checkbox(&mut self.try_alignments, "All alignments")
+                            .on_hover_text("Also decode with 1 to 3 leading characters skipped, for a character lost early on, and keep the alignment that decodes the most images");
+                        ui.checkbox(&mut self.decompress_stream, "Decompress")
+                            .on_hover_text("Inflate the decoded bytes before looking for images when they start with a gzip or zlib header");
+
+                        if previous_cleaning != (self.alphabet, self.cleaning_filter.clone(), self.recursive_scan) {
+                            self.base64_before_page = self.count_base64_before_page(self.current_page_index);
+                            self.clear_page_validity();
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.results_tab, ResultsTab::Stream, "Stream");
+                        ui.selectable_value(&mut self.results_tab, ResultsTab::Scratch, "Scratch");
+                    });
+
+                    if self.results_tab == ResultsTab::Scratch {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut self.scratch_text)
+                                .hint_text("Paste Base64 here")
+                                .desired_width(f32::INFINITY)
+                                .desired_rows(12)
+                                .font(egui::TextStyle::Monospace),
+                        );
+                        if ui.button("Decode Scratch").clicked() {
+                            self.run_scratch_decoding(ctx);
+                        }
+                        ui.separator();
+                    }
+
+                    if self.decode_rx.is_some() {
+                        ui.add(egui::ProgressBar::new(self.decode_progress).show_percentage());
+                    }
+
+                    // At-a-glance health of the whole stream
+                    if !self.decoded_bytes.is_empty() {
+                        ui.strong(format!(
+                            "Decoded {} bytes → {} image(s) ({} truncated)",
+                            format_thousands(self.decoded_bytes.len()),
+                            self.recovered_images.len(),
+                            self.truncated_segment_count
+                        ));
+                    }
                         
-                        // 1. Show Logs
-                        egui::CollapsingHeader::new("Processing Logs")
-                            .default_open(true)
-                            .show(ui, |ui| {
-                                for log in &self.decode_logs {
-                                    ui.label(log);
+                    // 1. Show Logs
+                    egui::CollapsingHeader::new("Processing Logs")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for log in &self.decode_logs {
+                                ui.label(log);
+                            }
+                        });
+
+                    egui::CollapsingHeader::new("Hex Dump")
+                        .default_open(false)
+                        .show(ui, |ui| self.show_hex_dump(ui));
+
+                    ui.separator();
+
+                    // 2. Show Recovered Images
+                    if self.recovered_images.is_empty() {
+                        ui.label("No images recovered.");
+                    } else {
+                        ui.label(format!("Recovered {} segments:", self.recovered_images.len()));
+                        ui.horizontal(|ui| {
+                            ui.label("Hide smaller than");
+                            ui.add(egui::DragValue::new(&mut self.image_min_size).range(0..=10_000).suffix(" px"))
+                                .on_hover_text("Width or height, to hide the tiny thumbnails a stream is full of");
+
+                            let mut formats: Vec<image::ImageFormat> = Vec::new();
+                            for format in self.recovered_images.iter().filter_map(RecoveredImage::format_or_guess) {
+                                if !formats.contains(&format) {
+                                    formats.push(format);
                                 }
-                            });
+                            }
+                            egui::ComboBox::from_id_salt("image_format_filter")
+                                .selected_text(match self.image_format_filter {
+                                    Some(format) => format!("{:?}", format),
+                                    None => "All formats".to_string(),
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.image_format_filter, None, "All formats");
+                                    for format in formats {
+                                        ui.selectable_value(&mut self.image_format_filter, Some(format), format!("{:?}", format));
+                                    }
+                                });
 
-                        ui.separator();
+                            let hidden = self
+                                .recovered_images
+                                .iter()
+                                .filter(|r| !r.is_shown(self.image_min_size, self.image_format_filter))
+                                .count();
+                            if hidden > 0 {
+                                ui.weak(format!("{} hidden by the filter", hidden));
+                            }
+                        });
 
-                        // 2. Show Recovered Images
-                        if self.decoded_textures.is_empty() {
-                            ui.label("No images recovered.");
-                        } else {
-                            ui.label(format!("Recovered {} segments:", self.decoded_textures.len()));
-                            for (i, texture) in self.decoded_textures.iter().enumerate() {
+                        let mut previous_source = None;
+                        for (i, recovered) in self.recovered_images.iter().enumerate() {
+                            if !recovered.is_shown(self.image_min_size, self.image_format_filter) {
+                                continue;
+                            }
+                            // Per-page mode: group the images under their page file
+                            if let Some(source) = &recovered.source {
+                                if previous_source != Some(source) {
+                                    ui.heading(source);
+                                    previous_source = Some(source);
+                                }
+                            }
+                            ui.horizontal(|ui| {
                                 ui.label(format!("Segment #{}", i + 1));
+                                if let Some(partial) = recovered.partial {
+                                    let badge = format!(
+                                        "{} — {:.0}% decoded",
+                                        if partial.truncated { "TRUNCATED" } else { "PARTIAL" },
+                                        partial.decoded_fraction * 100.0
+                                    );
+                                    ui.label(
+                                        egui::RichText::new(badge)
+                                            .strong()
+                                            .color(ui.visuals().warn_fg_color),
+                                    )
+                                    .on_hover_text("Only the top rows could be decoded, the gray rest is missing");
+                                }
+                            });
+                            if recovered.metadata.is_empty() {
+                                ui.weak("no metadata");
+                            } else {
+                                for (label, value) in &recovered.metadata {
+                                    ui.small(format!("{}: {}", label, value));
+                                }
+                            }
                                 
-                                // let size = texture.size_vec2();
-                                // let scale = (ui.available_width() / size.x).min(1.0); 
-                                ui.image(texture);
+                            let Some(texture) = &recovered.texture else {
+                                ui.weak(format!(
+                                    "{}x{} at offset 0x{:X}, texture freed (limit of {} in Settings)",
+                                    recovered.size[0],
+                                    recovered.size[1],
+                                    recovered.offset,
+                                    self.settings.max_image_textures
+                                ));
                                 ui.separator();
+                                continue;
+                            };
+
+                            // Small inline, click to see it at full size
+                            let response = ui
+                                .add(
+                                    egui::Image::new(texture)
+                                        .max_height(RECOVERED_THUMBNAIL_HEIGHT)
+                                        .sense(egui::Sense::click()),
+                                )
+                                .on_hover_cursor(egui::CursorIcon::ZoomIn);
+                            // Partial images get a frame in the badge color, to tell them apart at a glance
+                            if recovered.partial.is_some() {
+                                ui.painter().rect_stroke(
+                                    response.rect,
+                                    0.0,
+                                    egui::Stroke::new(3.0, ui.visuals().warn_fg_color),
+                                    egui::StrokeKind::Outside,
+                                );
+                            }
+                            if response.clicked() {
+                                self.viewed_image = Some(i);
+                                self.viewed_image_zoom = 1.0;
                             }
+                            ui.separator();
                         }
-                    });
+                    }
+                });
         });
 
         // --- FLOATING WINDOW FOR HEX JUMP ---
@@ -958,7 +4775,15 @@ impl eframe::App for PdfApp {
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
                 .show(ctx, |ui| {
-                    ui.label("Enter Hex Offset (e.g., 0x2E1B):");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.hex_jump_decimal, false, "Hex");
+                        ui.radio_value(&mut self.hex_jump_decimal, true, "Decimal");
+                    });
+                    if self.hex_jump_decimal {
+                        ui.label("Enter Decimal Offset (e.g., 11803):");
+                    } else {
+                        ui.label("Enter Hex Offset (e.g., 0x2E1B):");
+                    }
                     
                     // Input field
                     let response = ui.text_edit_singleline(&mut self.hex_input);
@@ -985,7 +4810,737 @@ impl eframe::App for PdfApp {
                     if !self.jump_status_msg.is_empty() {
                         ui.colored_label(egui::Color32::RED, &self.jump_status_msg);
                     }
+
+                    // The other direction: where the cursor is, to note it and come back later
+                    if let Some(offset) = self.get_cursor_binary_offset(ctx) {
+                        ui.separator();
+                        let offset_text = if self.hex_jump_decimal {
+                            offset.to_string()
+                        } else {
+                            format!("0x{:X}", offset)
+                        };
+                        ui.horizontal(|ui| {
+                            ui.label("Cursor is at");
+                            ui.monospace(&offset_text);
+                            if ui.button("Copy").clicked() {
+                                ctx.copy_text(offset_text.clone());
+                            }
+                        });
+                    }
+                });
+        }
+
+        // --- FIND AND REPLACE ---
+        if self.show_replace_dialog {
+            let mut open = true;
+            egui::Window::new("Find and Replace")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("replace_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("Find:");
+                        ui.text_edit_singleline(&mut self.find_input);
+                        ui.end_row();
+
+                        ui.label("Replace with:");
+                        ui.text_edit_singleline(&mut self.replace_input);
+                        ui.end_row();
+                    });
+
+                    ui.checkbox(&mut self.replace_whole_word, "Whole word");
+                    ui.checkbox(&mut self.replace_all_pages, "Also in the saved files of all other pages");
+
+                    if ui.button("Replace All").clicked() {
+                        self.perform_replace();
+                    }
+
+                    if !self.replace_status_msg.is_empty() {
+                        ui.label(&self.replace_status_msg);
+                    }
+                });
+            self.show_replace_dialog = open;
+        }
+
+        // --- PASSWORD FOR AN ENCRYPTED PDF ---
+        if let Some((paths, start_page)) = self.password_prompt.clone() {
+            egui::Window::new("Password Required")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(format!("{} is password protected.", paths.join(", ")));
+
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.password_input).password(true));
+                    if !response.has_focus() && self.password_input.is_empty() {
+                        response.request_focus();
+                    }
+                    let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Open").clicked() || submitted {
+                            self.password_prompt = None;
+                            self.pdf_password = Some(self.password_input.clone());
+                            self.open_documents(&paths, start_page);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.password_prompt = None;
+                        }
+                    });
+                });
+        }
+
+        // --- KEYBOARD SHORTCUTS ---
+        if self.show_shortcuts {
+            let mut open = true;
+            egui::Window::new("Keyboard Shortcuts")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    egui::Grid::new("shortcuts_grid").striped(true).show(ui, |ui| {
+                        for (keys, action) in SHORTCUTS {
+                            ui.strong(*keys);
+                            ui.label(*action);
+                            ui.end_row();
+                        }
+                    });
+                });
+            if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                open = false;
+            }
+            self.show_shortcuts = open;
+        }
+
+        // --- SETTINGS WINDOW ---
+        if self.show_settings {
+            let mut open = true;
+            egui::Window::new("Settings")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Ambiguous characters (Ctrl+J):");
+                        if ui.text_edit_singleline(&mut self.ambiguous_chars_input).changed() {
+                            let chars: Vec<char> = self
+                                .ambiguous_chars_input
+                                .chars()
+                                .filter(|c| !c.is_whitespace())
+                                .collect();
+                            if !chars.is_empty() {
+                                self.settings.ambiguous_chars = chars;
+                                self.settings.save();
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Page background:");
+                        let mut changed = ui.color_edit_button_srgb(&mut self.settings.render_background).changed();
+                        changed |= ui.checkbox(&mut self.settings.render_antialiasing, "Anti-aliasing").changed();
+                        changed |= ui.checkbox(&mut self.settings.render_grayscale, "Grayscale").changed();
+                        if ui.button("High contrast").on_hover_text("Black on white, no anti-aliasing").clicked() {
+                            self.settings.render_background = [255, 255, 255];
+                            self.settings.render_antialiasing = false;
+                            self.settings.render_grayscale = true;
+                            changed = true;
+                        }
+                        if changed {
+                            self.settings.save();
+                            self.rerender_pages();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Highlight:");
+                        let mut changed = ui.color_edit_button_srgb(&mut self.settings.highlight_color).changed();
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.settings.highlight_width).range(0.5..=20.0).suffix(" px"))
+                            .changed();
+                        changed |= ui
+                            .radio_value(&mut self.settings.highlight_style, HighlightStyle::Underline, "Underline")
+                            .changed();
+                        changed |= ui
+                            .radio_value(&mut self.settings.highlight_style, HighlightStyle::Outline, "Outline")
+                            .changed();
+                        if changed {
+                            self.settings.save();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Display script:");
+                        if ui
+                            .add(egui::TextEdit::singleline(&mut self.settings.display_script).hint_text("display_script.sh"))
+                            .on_hover_text("Run by the Display button (Ctrl+D) in the page files directory, empty for the default")
+                            .changed()
+                        {
+                            self.settings.save();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Placeholder (Ctrl+I):");
+                        let mut placeholder = self.settings.placeholder_char.to_string();
+                        if ui
+                            .add(egui::TextEdit::singleline(&mut placeholder).desired_width(20.0))
+                            .on_hover_text("Inserted for a dropped character, a valid one keeps the decode aligned")
+                            .changed()
+                        {
+                            // The last char typed replaces the old one
+                            if let Some(c) = placeholder.chars().rev().find(|c| !c.is_whitespace()) {
+                                self.settings.placeholder_char = c;
+                                self.settings.save();
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Page files:");
+                        let mut changed = ui
+                            .add(egui::TextEdit::singleline(&mut self.settings.page_file_prefix).desired_width(60.0))
+                            .changed();
+                        ui.label("NNN");
+                        changed |= ui
+                            .add(egui::TextEdit::singleline(&mut self.settings.page_file_suffix).desired_width(60.0))
+                            .changed();
+                        if changed {
+                            self.settings.save();
+                            // The files of the other pages may be different ones now
+                            self.clear_page_validity();
+                        }
+                    })
+                    .response
+                    .on_hover_text("Page files are named prefix + 3 digit page number + suffix, e.g. page007.txt or p_007.out");
+                    ui.horizontal(|ui| {
+                        ui.label("Base64 line length:");
+                        if ui
+                            .add(egui::DragValue::new(&mut self.settings.line_length).range(4..=1000))
+                            .on_hover_text("Lines with exactly this many characters are marked green (76 for MIME, 64 for PEM)")
+                            .changed()
+                        {
+                            self.clear_page_validity();
+                            self.settings.save();
+                        }
+                    });
+                    if ui
+                        .checkbox(&mut self.settings.keep_carriage_returns, "Keep carriage returns")
+                        .on_hover_text("Don't replace CR (0x0D) with spaces when loading a page, applies to the next page loaded")
+                        .changed()
+                    {
+                        self.settings.save();
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Line endings on save:");
+                        let mut changed = ui.radio_value(&mut self.settings.line_ending, LineEnding::Lf, "LF").changed();
+                        changed |= ui
+                            .radio_value(&mut self.settings.line_ending, LineEnding::Crlf, "CRLF")
+                            .on_hover_text("Windows line endings")
+                            .changed();
+                        if changed {
+                            self.settings.save();
+                        }
+                    });
+                    if ui
+                        .checkbox(&mut self.settings.save_on_page_change, "Save edits when changing pages")
+                        .on_hover_text("Otherwise you are asked whether to save or discard them")
+                        .changed()
+                    {
+                        self.settings.save();
+                    }
+                    if ui
+                        .checkbox(&mut self.settings.partial_jpeg_recovery, "Partial JPEG recovery")
+                        .on_hover_text("When a JPEG fails to decode, show the rows before the corruption (on the next decode)")
+                        .changed()
+                    {
+                        self.settings.save();
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Recovered image textures:");
+                        if ui
+                            .add(egui::DragValue::new(&mut self.settings.max_image_textures).range(1..=10_000))
+                            .on_hover_text("Textures of the oldest recovered images are freed above this, to save GPU memory")
+                            .changed()
+                        {
+                            self.enforce_texture_limit();
+                            self.settings.save();
+                        }
+                    });
+                });
+            self.show_settings = open;
+        }
+
+        // --- LINES THAT NEED ATTENTION ---
+        if self.show_problem_lines {
+            let mut open = true;
+            egui::Window::new("Problem Lines")
+                .open(&mut open)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    let problems: Vec<(usize, LineStatus)> = self
+                        .text_content
+                        .lines()
+                        .map(|line| LineStatus::of(line, self.alphabet, self.settings.line_length))
+                        .enumerate()
+                        .filter(|(_, status)| *status != LineStatus::Valid)
+                        .collect();
+
+                    if problems.is_empty() {
+                        ui.label("All lines look valid.");
+                        return;
+                    }
+
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::Grid::new("problem_lines_grid").striped(true).show(ui, |ui| {
+                            for (line_idx, status) in problems {
+                                ui.monospace(format!("Line {}", line_idx + 1));
+                                ui.colored_label(status.color(ui.visuals().dark_mode), status.describe());
+                                if ui.button("Go").clicked() {
+                                    Self::move_cursor_to(
+                                        ctx,
+                                        Self::line_start_char_index(&self.text_content, line_idx),
+                                    );
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+            self.show_problem_lines = open;
+        }
+
+        // --- PDF TEXT VS EDITOR ---
+        if self.show_pdf_text {
+            let mut open = true;
+            egui::Window::new("PDF Text")
+                .open(&mut open)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.pdf_text_raw, false, "Diff");
+                        ui.selectable_value(&mut self.pdf_text_raw, true, "PDF text");
+                    });
+                    ui.separator();
+
+                    if self.pdf_text.is_empty() {
+                        ui.label("No text was extracted from this page.");
+                    } else if self.pdf_text_raw {
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            ui.monospace(&self.pdf_text);
+                        });
+                    } else {
+                        let lines = diff::diff_lines(&self.pdf_text, &self.text_content);
+                        Self::show_diff(ui, ctx, &lines, &self.text_content, "PDF");
+                    }
+                });
+            self.show_pdf_text = open;
+        }
+
+        // --- EDITOR VS ANOTHER PAGE ---
+        if self.show_page_diff {
+            let mut open = true;
+            egui::Window::new("Compare Page")
+                .open(&mut open)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Page {} (editor) vs page", self.current_page_index + 1));
+                        let mut page = self.compare_page_index + 1;
+                        if ui
+                            .add(egui::DragValue::new(&mut page).range(1..=self.total_pages.max(1)))
+                            .changed()
+                        {
+                            self.compare_page_index = page - 1;
+                            self.load_compare_page();
+                        }
+                        if ui.button("Reload").on_hover_text("Read the page file again").clicked() {
+                            self.load_compare_page();
+                        }
+                    });
+                    ui.separator();
+
+                    let lines = diff::diff_lines(&self.compare_page_text, &self.text_content);
+                    let other_name = format!("page {}", self.compare_page_index + 1);
+                    Self::show_diff(ui, ctx, &lines, &self.text_content, &other_name);
+                });
+            self.show_page_diff = open;
+        }
+
+        // --- PLACEHOLDERS FOR DROPPED CHARS ---
+        if self.show_placeholders {
+            let mut open = true;
+            egui::Window::new("Placeholders")
+                .open(&mut open)
+                .default_height(300.0)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Insert at Cursor")
+                            .on_hover_text(format!(
+                                "Insert '{}' where a character is missing (Ctrl+I), the character is set in Settings",
+                                self.settings.placeholder_char
+                            ))
+                            .clicked()
+                        {
+                            self.insert_placeholder(ctx);
+                        }
+                        if ui
+                            .add_enabled(self.decode_rx.is_none(), egui::Button::new("Try All at Cursor"))
+                            .on_hover_text("Decode with every possible value of the character after the cursor and rank them")
+                            .clicked()
+                        {
+                            self.run_char_search_at_cursor(ctx);
+                        }
+                        if !self.placeholders.is_empty() && ui.button("Forget All").clicked() {
+                            self.placeholders.clear();
+                        }
+                    });
+                    ui.separator();
+
+                    let mut forget = None;
+                    let mut try_all = None;
+                    if self.placeholders.is_empty() {
+                        ui.label("No placeholders on this page. Typing over one removes it from the list.");
+                    }
+                    egui::ScrollArea::vertical().id_salt("placeholders_scroll").max_height(150.0).show(ui, |ui| {
+                        egui::Grid::new("placeholders_grid").striped(true).show(ui, |ui| {
+                            for (i, &position) in self.placeholders.iter().enumerate() {
+                                let (line, column) = Self::line_and_column(&self.text_content, position);
+                                ui.monospace(format!("Line {}, column {}", line + 1, column + 1));
+                                if ui.button("Go").clicked() {
+                                    Self::move_cursor_to(ctx, position);
+                                    self.scroll_to_cursor = true;
+                                }
+                                if ui
+                                    .add_enabled(self.decode_rx.is_none(), egui::Button::new("Try All"))
+                                    .on_hover_text("Decode with every possible value here and rank them")
+                                    .clicked()
+                                {
+                                    try_all = Some(position);
+                                }
+                                if ui.button("Forget").on_hover_text("Keep the character, drop it from the list").clicked() {
+                                    forget = Some(i);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    if let Some(i) = forget {
+                        self.placeholders.remove(i);
+                    }
+                    if let Some(position) = try_all {
+                        self.run_char_search(ctx, position);
+                    }
+
+                    // Results of the last Try All, while they still point into this page
+                    let Some(search) = self.char_search.as_ref().filter(|s| s.page == self.current_page_index) else {
+                        if self.decode_rx.is_some() {
+                            ui.separator();
+                            ui.label(format!("Decoding... {:.0}%", self.decode_progress * 100.0));
+                        }
+                        return;
+                    };
+                    ui.separator();
+                    let (line, column) = Self::line_and_column(&self.text_content, search.position);
+                    let current = self.text_content.chars().nth(search.position);
+                    ui.label(format!("Values for line {}, column {}, best first:", line + 1, column + 1));
+                    let mut use_value = None;
+                    egui::ScrollArea::vertical().id_salt("char_search_scroll").show(ui, |ui| {
+                        egui::Grid::new("char_search_grid").striped(true).show(ui, |ui| {
+                            for candidate in &search.ranked {
+                                ui.monospace(candidate.value.to_string());
+                                ui.label(format!("{} complete image(s)", candidate.complete_images));
+                                ui.label(format!("{} JPEG row(s)", candidate.jpeg_rows));
+                                if current == Some(candidate.value) {
+                                    ui.label("(current)");
+                                } else if ui.button("Use").clicked() {
+                                    use_value = Some((search.position, candidate.value));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    if let Some((position, value)) = use_value {
+                        self.use_char_candidate(position, value);
+                    }
+                });
+            self.show_placeholders = open;
+        }
+
+        // --- BASE64 CHAR FREQUENCIES ---
+        if self.show_histogram {
+            let mut open = true;
+            egui::Window::new("Char Histogram")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.histogram_whole_document, false, "Current page");
+                        if ui
+                            .selectable_value(&mut self.histogram_whole_document, true, "Whole document")
+                            .on_hover_text("The saved page files, the edits of the current page count once saved")
+                            .clicked()
+                        {
+                            self.count_document_histogram();
+                        }
+                        if self.histogram_whole_document && ui.button("Recount").clicked() {
+                            self.count_document_histogram();
+                        }
+                    });
+                    ui.separator();
+
+                    let counts = if self.histogram_whole_document {
+                        self.document_histogram.clone()
+                    } else {
+                        let clean = decode::clean_page(&self.text_content, self.alphabet, &self.cleaning_filter);
+                        decode::char_histogram(&clean, self.alphabet)
+                    };
+                    let [r, g, b] = self.settings.highlight_color;
+                    Self::show_histogram_chart(
+                        ui,
+                        &counts,
+                        &self.settings.ambiguous_chars,
+                        egui::Color32::from_rgb(r, g, b),
+                    );
+                });
+            self.show_histogram = open;
+        }
+
+        self.show_toast(ctx);
+
+        // --- RECOVERED IMAGE AT NATIVE RESOLUTION ---
+        if let Some(index) = self.viewed_image {
+            // Gone when the texture was freed to stay under the limit
+            if let Some(texture) = self.recovered_images.get(index).and_then(|r| r.texture.clone()) {
+                let size = texture.size_vec2();
+                let mut open = true;
+                egui::Window::new(format!("Segment #{} ({}x{})", index + 1, size.x, size.y))
+                    .id(egui::Id::new("recovered_image_viewer"))
+                    .open(&mut open)
+                    .default_size(egui::vec2(800.0, 600.0))
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::Slider::new(&mut self.viewed_image_zoom, 0.1..=8.0)
+                                    .logarithmic(true)
+                                    .text("Zoom"),
+                            );
+                            if ui.button("1:1").clicked() {
+                                self.viewed_image_zoom = 1.0;
+                            }
+                        });
+
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            let response = ui.add(
+                                egui::Image::new(&texture)
+                                    .fit_to_exact_size(size * self.viewed_image_zoom),
+                            );
+                            // Ctrl+scroll zooms like in the PDF view
+                            if response.hovered() {
+                                let zoom_delta = ui.input(|i| i.zoom_delta());
+                                self.viewed_image_zoom = (self.viewed_image_zoom * zoom_delta).clamp(0.1, 8.0);
+                            }
+                        });
+                    });
+                if !open {
+                    self.viewed_image = None;
+                }
+            } else {
+                self.viewed_image = None;
+            }
+        }
+
+        // --- CONFIRMATION BEFORE RELOAD THROWS AWAY UNSAVED EDITS ---
+        if self.show_reload_dialog {
+            egui::Window::new("Reload Page")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Page {} has unsaved edits, reloading replaces them with {}",
+                        self.current_page_index + 1,
+                        self.page_file_path(self.current_page_index).display()
+                    ));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Reload").clicked() {
+                            self.show_reload_dialog = false;
+                            self.reload_page_file();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_reload_dialog = false;
+                        }
+                    });
+                });
+        }
+
+        // --- UNSAVED EDITS WHEN LEAVING THE PAGE ---
+        if let Some(index) = self.pending_page {
+            egui::Window::new("Unsaved Changes")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Page {} has unsaved edits. Save them before going to page {}?",
+                        self.current_page_index + 1,
+                        index + 1
+                    ));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            self.pending_page = None;
+                            self.save_page();
+                            self.load_page(index);
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.pending_page = None;
+                            self.load_page(index);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.pending_page = None;
+                        }
+                    });
+                });
+        }
+
+        // --- CONFIRMATION BEFORE SAVE ALL OVERWRITES EDITED FILES ---
+        if self.show_save_all_dialog {
+            egui::Window::new("Save All Pages")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    let pages: Vec<String> = self
+                        .save_all_conflicts
+                        .iter()
+                        .map(|i| format!("{}", i + 1))
+                        .collect();
+                    ui.label(format!(
+                        "{} saved page file(s) differ from the PDF text: {}",
+                        pages.len(),
+                        pages.join(", ")
+                    ));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Overwrite").clicked() {
+                            self.show_save_all_dialog = false;
+                            self.save_all_pages(true);
+                        }
+                        if ui.button("Keep edited files").clicked() {
+                            self.show_save_all_dialog = false;
+                            self.save_all_pages(false);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_save_all_dialog = false;
+                            self.save_all_conflicts.clear();
+                        }
+                    });
                 });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty folder of its own for every test, tests run in parallel
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pdfbase64tofile_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn default_pattern() -> PageFilePattern {
+        PageFilePattern::of(&Settings::default())
+    }
+
+    #[test]
+    fn page_numbers_parse_whatever_the_padding() {
+        let pattern = default_pattern();
+        assert_eq!(pattern.number_of(Path::new("page1.txt")), Some(1));
+        assert_eq!(pattern.number_of(Path::new("page02.txt")), Some(2));
+        assert_eq!(pattern.number_of(Path::new("page100.txt")), Some(100));
+        assert_eq!(pattern.number_of(Path::new("page.txt")), None);
+    }
+
+    #[test]
+    fn page_files_sort_numerically() {
+        let dir = test_dir("sort");
+        for name in ["page100.txt", "page.txt", "page02.txt", "page1.txt", "decode_log.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = list_page_files(&dir, false, &default_pattern())
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        // page.txt has no number, it goes after every numbered page
+        assert_eq!(names, ["page1.txt", "page02.txt", "page100.txt", "page.txt"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn starts_at_the_latest_saved_page() {
+        let dir = test_dir("latest");
+        for name in ["page001.txt", "page002.txt", "page010.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        // page010.txt is the 10th page, index 9
+        assert_eq!(PdfApp::latest_index(&dir, &default_pattern()), 9);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn starts_at_the_first_page_without_files() {
+        let dir = test_dir("latest_empty");
+        assert_eq!(PdfApp::latest_index(&dir, &default_pattern()), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // The accented chars take two bytes each, the jump must still land on the 'l'
+    #[test]
+    fn jumps_over_multibyte_chars() {
+        let targets = ['I', 'l', '1'];
+        assert_eq!(PdfApp::next_ambiguous_char("éèl", 0, &targets), Some(2));
+        assert_eq!(PdfApp::next_ambiguous_char("éèlAl", 2, &targets), Some(4));
+        assert_eq!(PdfApp::next_ambiguous_char("éèl", 2, &targets), None);
+    }
+
+    // Written like save_page, read back like load_page
+    fn save_and_load(dir: &Path, text: &str, line_ending: LineEnding, keep_carriage_returns: bool) -> String {
+        let path = dir.join("page001.txt");
+        fs::write(&path, line_ending.normalize(text)).unwrap();
+        PdfApp::clean_carriage_returns(&fs::read_to_string(&path).unwrap(), keep_carriage_returns)
+    }
+
+    #[test]
+    fn kept_carriage_returns_survive_save_and_load() {
+        let dir = test_dir("carriage_returns");
+        let text = "QUJD\r\nRUZH\rSUpL\r\n";
+
+        assert_eq!(save_and_load(&dir, text, LineEnding::Crlf, true), text);
+        // LF only rewrites the line breaks, the CR in the middle of the line is data
+        assert_eq!(save_and_load(&dir, text, LineEnding::Lf, true), "QUJD\nRUZH\rSUpL\n");
+        // Not kept, the CRLF line breaks load as LF and only the lone CR becomes a space
+        assert_eq!(save_and_load(&dir, text, LineEnding::Crlf, false), "QUJD\nRUZH SUpL\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Every save and load must give back the same text, not one more trailing space per line
+    #[test]
+    fn crlf_saves_round_trip_without_kept_carriage_returns() {
+        let dir = test_dir("crlf_round_trip");
+        let loaded = save_and_load(&dir, "QUJD\r\nRUZH\rSUpL\r\n", LineEnding::Crlf, false);
+        let reloaded = save_and_load(&dir, &loaded, LineEnding::Crlf, false);
+        assert_eq!(reloaded, loaded);
+        assert_eq!(save_and_load(&dir, &reloaded, LineEnding::Crlf, false), loaded);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_lone_carriage_return_is_not_a_line_break() {
+        assert_eq!(LineEnding::Crlf.normalize("AB\rCD\nEF"), "AB\rCD\r\nEF");
+        assert_eq!(LineEnding::Crlf.normalize("AB\r\nCD"), "AB\r\nCD");
+        assert_eq!(LineEnding::Lf.normalize("AB\rCD\r\nEF"), "AB\rCD\nEF");
+    }
+}
@@ -0,0 +1,238 @@
+use std::fs;
+
+// Stored next to the binary's working directory, like libpdfium and the display script
+const SETTINGS_FILE: &str = "pdfbase64tofile.cfg";
+
+// How the chars selected in the editor are marked on the PDF view
+#[derive(Clone, Copy, PartialEq)]
+pub enum HighlightStyle {
+    Underline,
+    Outline, // Rectangle around every char
+}
+
+// Line breaks written to the page files
+#[derive(Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    // Every LF and CRLF line break turned into this one, a CR on its own is data and stays
+    pub fn normalize(self, text: &str) -> String {
+        let lf = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => lf,
+            LineEnding::Crlf => lf.replace('\n', "\r\n"),
+        }
+    }
+}
+
+// User preferences that survive restarts.
+// The file is a plain `key = value` list, unknown keys are ignored.
+#[derive(Clone)]
+pub struct Settings {
+    // Characters Ctrl+J jumps between (OCR often confuses them)
+    pub ambiguous_chars: Vec<char>,
+    // Expected number of Base64 chars on every line
+    pub line_length: usize,
+    // Keep CR (0x0D) in the editor instead of turning it into a space
+    pub keep_carriage_returns: bool,
+    // Line breaks of the saved page files, whatever the editor holds
+    pub line_ending: LineEnding,
+    // Save unsaved edits without asking when moving to another page
+    pub save_on_page_change: bool,
+    // Show the rows decoded before the corruption when a JPEG fails the strict decode
+    pub partial_jpeg_recovery: bool,
+    // Recovered images kept on the GPU, the textures of the oldest ones are freed above this
+    pub max_image_textures: usize,
+    // Run by the Display button, empty for display_script.sh (.bat on Windows)
+    pub display_script: String,
+    // Page files are prefix + 3 digit page number + suffix, "page007.txt" by default
+    pub page_file_prefix: String,
+    pub page_file_suffix: String,
+    // Inserted by Ctrl+I for a char OCR dropped whose value isn't known yet
+    pub placeholder_char: char,
+    // Monospace editor font, big enough to tell I/l/1 apart
+    pub editor_font_size: f32,
+    pub dark_mode: bool,
+    pub highlight_color: [u8; 3], // sRGB, stored as #RRGGBB
+    pub highlight_width: f32,     // Stroke width in pixels
+    pub highlight_style: HighlightStyle,
+    pub render_background: [u8; 3], // Behind the page content, sRGB stored as #RRGGBB
+    pub render_antialiasing: bool,
+    pub render_grayscale: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ambiguous_chars: vec!['I', 'l', '1'],
+            line_length: 76,
+            keep_carriage_returns: false,
+            line_ending: LineEnding::Lf,
+            save_on_page_change: false,
+            partial_jpeg_recovery: true,
+            max_image_textures: 64,
+            display_script: String::new(),
+            page_file_prefix: "page".to_string(),
+            page_file_suffix: ".txt".to_string(),
+            placeholder_char: 'A',
+            editor_font_size: 24.0,
+            dark_mode: true,
+            highlight_color: [0, 255, 0],
+            highlight_width: 4.0,
+            highlight_style: HighlightStyle::Underline,
+            render_background: [255, 255, 255],
+            render_antialiasing: true,
+            render_grayscale: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+
+        if let Ok(content) = fs::read_to_string(SETTINGS_FILE) {
+            for line in content.lines() {
+                if let Some((key, value)) = line.split_once('=') {
+                    settings.apply(key.trim(), value.trim());
+                }
+            }
+        }
+
+        settings
+    }
+
+    fn apply(&mut self, key: &str, value: &str) {
+        match key {
+            "ambiguous_chars" => {
+                let chars: Vec<char> = value.chars().filter(|c| !c.is_whitespace()).collect();
+                if !chars.is_empty() {
+                    self.ambiguous_chars = chars;
+                }
+            }
+            "line_length" => match value.parse::<usize>() {
+                Ok(length) if length > 0 => self.line_length = length,
+                _ => eprintln!("line_length expects a positive number, got {}", value),
+            },
+            "keep_carriage_returns" => match value.parse() {
+                Ok(keep) => self.keep_carriage_returns = keep,
+                Err(_) => eprintln!("keep_carriage_returns expects true or false, got {}", value),
+            },
+            "line_ending" => match value {
+                "lf" => self.line_ending = LineEnding::Lf,
+                "crlf" => self.line_ending = LineEnding::Crlf,
+                _ => eprintln!("line_ending expects lf or crlf, got {}", value),
+            },
+            "save_on_page_change" => match value.parse() {
+                Ok(save) => self.save_on_page_change = save,
+                Err(_) => eprintln!("save_on_page_change expects true or false, got {}", value),
+            },
+            "partial_jpeg_recovery" => match value.parse() {
+                Ok(enabled) => self.partial_jpeg_recovery = enabled,
+                Err(_) => eprintln!("partial_jpeg_recovery expects true or false, got {}", value),
+            },
+            "max_image_textures" => match value.parse::<usize>() {
+                Ok(count) if count > 0 => self.max_image_textures = count,
+                _ => eprintln!("max_image_textures expects a positive number, got {}", value),
+            },
+            "display_script" => self.display_script = value.to_string(),
+            "page_file_prefix" => self.page_file_prefix = value.to_string(),
+            "page_file_suffix" => self.page_file_suffix = value.to_string(),
+            "placeholder_char" => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => self.placeholder_char = c,
+                    _ => eprintln!("placeholder_char expects a single character, got {}", value),
+                }
+            }
+            "editor_font_size" => match value.parse::<f32>() {
+                Ok(size) if size > 0.0 => self.editor_font_size = size,
+                _ => eprintln!("editor_font_size expects a positive number, got {}", value),
+            },
+            "dark_mode" => match value.parse() {
+                Ok(dark) => self.dark_mode = dark,
+                Err(_) => eprintln!("dark_mode expects true or false, got {}", value),
+            },
+            "highlight_color" => match parse_color(value) {
+                Some(color) => self.highlight_color = color,
+                None => eprintln!("highlight_color expects #RRGGBB, got {}", value),
+            },
+            "highlight_width" => match value.parse::<f32>() {
+                Ok(width) if width > 0.0 => self.highlight_width = width,
+                _ => eprintln!("highlight_width expects a positive number, got {}", value),
+            },
+            "highlight_style" => match value {
+                "underline" => self.highlight_style = HighlightStyle::Underline,
+                "outline" => self.highlight_style = HighlightStyle::Outline,
+                _ => eprintln!("highlight_style expects underline or outline, got {}", value),
+            },
+            "render_background" => match parse_color(value) {
+                Some(color) => self.render_background = color,
+                None => eprintln!("render_background expects #RRGGBB, got {}", value),
+            },
+            "render_antialiasing" => match value.parse() {
+                Ok(enabled) => self.render_antialiasing = enabled,
+                Err(_) => eprintln!("render_antialiasing expects true or false, got {}", value),
+            },
+            "render_grayscale" => match value.parse() {
+                Ok(enabled) => self.render_grayscale = enabled,
+                Err(_) => eprintln!("render_grayscale expects true or false, got {}", value),
+            },
+            _ => eprintln!("Ignoring unknown setting {}", key),
+        }
+    }
+
+    pub fn save(&self) {
+        let content = format!(
+            "ambiguous_chars = {}\nline_length = {}\nkeep_carriage_returns = {}\nline_ending = {}\nsave_on_page_change = {}\npartial_jpeg_recovery = {}\nmax_image_textures = {}\ndisplay_script = {}\npage_file_prefix = {}\npage_file_suffix = {}\nplaceholder_char = {}\neditor_font_size = {}\ndark_mode = {}\n\
+             highlight_color = #{:02X}{:02X}{:02X}\nhighlight_width = {}\nhighlight_style = {}\n\
+             render_background = #{:02X}{:02X}{:02X}\nrender_antialiasing = {}\nrender_grayscale = {}\n",
+            self.ambiguous_chars.iter().collect::<String>(),
+            self.line_length,
+            self.keep_carriage_returns,
+            match self.line_ending {
+                LineEnding::Lf => "lf",
+                LineEnding::Crlf => "crlf",
+            },
+            self.save_on_page_change,
+            self.partial_jpeg_recovery,
+            self.max_image_textures,
+            self.display_script,
+            self.page_file_prefix,
+            self.page_file_suffix,
+            self.placeholder_char,
+            self.editor_font_size,
+            self.dark_mode,
+            self.highlight_color[0],
+            self.highlight_color[1],
+            self.highlight_color[2],
+            self.highlight_width,
+            match self.highlight_style {
+                HighlightStyle::Underline => "underline",
+                HighlightStyle::Outline => "outline",
+            },
+            self.render_background[0],
+            self.render_background[1],
+            self.render_background[2],
+            self.render_antialiasing,
+            self.render_grayscale
+        );
+
+        if let Err(e) = fs::write(SETTINGS_FILE, content) {
+            eprintln!("Error saving settings to {}: {}", SETTINGS_FILE, e);
+        }
+    }
+}
+
+// "#00FF00" -> [0, 255, 0]
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}